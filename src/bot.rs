@@ -1,202 +1,1495 @@
 use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
     fmt::Display,
+    str::FromStr,
     sync::{
-        mpsc::{channel, Receiver},
+        atomic::Ordering,
+        mpsc::{channel, Receiver, TryRecvError},
         Arc,
     },
     thread,
+    time::{Duration, Instant, SystemTime},
 };
 
-use binance::{api::Binance, errors::Result as BinanceResult, market::Market, model::PriceStats};
+use binance::{
+    api::{Spot, API},
+    errors::{Error as BinanceError, Result as BinanceResult},
+    market::Market,
+    model::{AggrTradesEvent, OrderBook},
+    websockets::{WebSockets, WebsocketEvent},
+};
 
-use crate::error::Result;
+use crate::{
+    connection::{self, Backoff, ConnectionState, Readers},
+    currency::{Conversion, Currency},
+    decimal::Decimal,
+    error::{Error, Result},
+    exchange::{self, ExchangeInfo, SymbolInfo},
+    format::Precision,
+    metrics,
+    save::SaveData,
+    symbol::Symbol,
+};
 
 pub struct Bot {
-    market: Arc<Market>,
+    readers: Readers,
 
     symbol: Symbol,
     live_stats_tracker: LiveStatsTracker,
+    depth_tracker: DepthTracker,
+    trade_tracker: TradeTracker,
+    /// Trades with at least this notional value are highlighted.
+    large_trade_notional: Decimal,
+    /// Trackers of the other watched symbols.
+    watchlist: Vec<LiveStatsTracker>,
+    /// Currency the prices are also shown in or `None` for only the quote assets.
+    currency: Option<Currency>,
+    /// Conversions of the quote assets into the currencies, keyed by both assets.
+    conversions: HashMap<(String, String), Conversion>,
+    /// Trackers of the symbols which aren't watched but are needed by the conversions.
+    rate_trackers: Vec<LiveStatsTracker>,
+    /// Prices of the main symbol which weren't read for this long raise an alert.
+    stale_alert_after: Option<Duration>,
+    /// Whether the current stale prices were already alerted.
+    stale_alerted: bool,
+    started: Instant,
 
-    price_triggers: Vec<PriceTrigger>,
-    latest_alerts: Vec<Alert>,
+    /// Symbols listed on the exchange or `None` until they are known.
+    exchange: Option<Arc<ExchangeInfo>>,
+    /// Fetches the exchange info when it is missing or stale.
+    exchange_reader: Option<Receiver<Result<ExchangeInfo>>>,
+    /// When the exchange info was last requested.
+    exchange_requested: Option<Instant>,
 
+    price_triggers: Vec<PriceTrigger>,
+    latest_alerts: AlertLog,
+    /// Errors which happened since they were last taken.
+    errors: Vec<Error>,
     //tick: u16,
 }
 
 impl Bot {
     //const TICKS_PER_UPDATE: u16 = 1;
-    const DEFAULT_SYMBOL: &str = "ETHUSDT";
+    pub const DEFAULT_SYMBOL: &str = "ETHUSDT";
+    const DEFAULT_LARGE_TRADE_NOTIONAL: Decimal = Decimal::new(50_000, 0);
+    /// A failed request of the exchange info is retried after this long.
+    const EXCHANGE_INFO_RETRY: Duration = Duration::from_secs(60 * 60);
+    /// Decimals of the imbalance percents compared with the trigger levels.
+    const IMBALANCE_SCALE: u32 = 4;
 
-    pub fn with_symbol<S: Into<Symbol>>(symbol: S) -> Result<Self> {
-        let symbol = symbol.into();
-        let market = Arc::new(Market::new(None, None));
-        let live_stats_tracker = LiveStatsTracker::new(market.clone(), symbol);
+    /// Creates a bot tracking the `symbol` whose market data is read every `poll_interval`.
+    ///
+    /// The symbols are checked against the cached exchange info until it is fetched again.
+    pub fn with_symbol(symbol: Symbol, poll_interval: Duration) -> Result<Self> {
+        let readers = Readers::new(poll_interval);
+        let live_stats_tracker = LiveStatsTracker::new(readers.clone(), symbol.clone());
+        let depth_tracker = DepthTracker::new(readers.clone(), symbol.clone());
         Ok(Self {
-            market,
+            trade_tracker: TradeTracker::new(symbol.clone(), readers.clone()),
+            readers,
 
             symbol,
             live_stats_tracker,
+            depth_tracker,
+            large_trade_notional: Self::DEFAULT_LARGE_TRADE_NOTIONAL,
+            watchlist: Vec::new(),
+            currency: None,
+            conversions: HashMap::new(),
+            rate_trackers: Vec::new(),
+            stale_alert_after: None,
+            stale_alerted: false,
+            started: Instant::now(),
 
-            price_triggers: vec![
-                PriceTrigger {
-                    price: PriceLevel(1210.0),
-                    condition: TriggerCondition::HigherEq,
-                },
-                PriceTrigger {
-                    price: PriceLevel(1208.0),
-                    condition: TriggerCondition::LowerEq,
-                },
-                PriceTrigger {
-                    price: PriceLevel(1209.0),
-                    condition: TriggerCondition::HigherEq,
-                },
-            ],
-            latest_alerts: Vec::new(),
+            exchange: exchange::load_cache().map(Arc::new),
+            exchange_reader: None,
+            exchange_requested: None,
 
+            price_triggers: Vec::new(),
+            latest_alerts: AlertLog::default(),
+            errors: Vec::new(),
             //tick: 0,
         })
     }
 
-    pub fn new() -> Result<Self> {
-        Self::with_symbol(Self::DEFAULT_SYMBOL)
-    }
-
+    /// Raises the alerts of the triggers whose condition started to hold.
+    ///
+    /// A trigger fires once when its condition starts to hold and fires again only
+    /// after the condition stopped holding, the same way as the stale alert.
     pub fn analyze(&mut self) {
-        let price = self.live_stats().last_price;
+        // Price triggers wait until the price of their symbol is read for the first time
+        let prices = self
+            .watchlist()
+            .filter(|tracker| tracker.updated().is_some())
+            .map(|tracker| (tracker.symbol(), tracker.stats().last_price))
+            .collect::<Vec<(Symbol, Decimal)>>();
+        // Imbalance triggers wait until the order book is read for the first time
+        let imbalance = self.depth_tracker.imbalance();
+        // Only the trades received since the last analysis are compared
+        let largest_trade = self
+            .trade_tracker
+            .new_trades()
+            .iter()
+            .filter_map(Trade::notional)
+            .max();
 
-        let triggered = self
+        // Whether the condition of each trigger holds or `None` while it isn't known
+        let conditions = self
             .price_triggers
-            .iter().enumerate()
-            .filter(|(i, trigger)| match trigger.condition {
-                TriggerCondition::HigherEq => price >= trigger.price.0,
-                TriggerCondition::LowerEq => price <= trigger.price.0,
+            .iter()
+            .map(|trigger| {
+                let value = match trigger.target {
+                    TriggerTarget::Price => {
+                        let symbol = trigger.symbol.as_ref().unwrap_or(&self.symbol);
+                        let (_, price) = prices.iter().find(|(s, _)| s == symbol)?;
+                        // Converted triggers also wait for the prices of the conversion
+                        match &trigger.currency {
+                            Some(currency) => price.checked_mul(self.rate(symbol, currency)?)?,
+                            None => *price,
+                        }
+                    }
+                    TriggerTarget::Imbalance => imbalance?,
+                    // Without new trades there is no trade above the level
+                    TriggerTarget::Trade => match largest_trade {
+                        Some(notional) => notional,
+                        None => return Some(false),
+                    },
+                };
+                Some(match trigger.condition {
+                    TriggerCondition::HigherEq => value >= trigger.price.0,
+                    TriggerCondition::LowerEq => value <= trigger.price.0,
+                })
             })
-            .collect::<Vec<(usize, &PriceTrigger)>>();
+            .collect::<Vec<Option<bool>>>();
+
+        for (trigger, holds) in self.price_triggers.iter_mut().zip(conditions) {
+            if !trigger.fires(holds) {
+                continue;
+            }
+            metrics::count_alert(trigger.target.name());
+            self.latest_alerts.push(Alert::new(trigger));
+        }
 
-        if !triggered.is_empty() {
-            for (i, t) in triggered {
-                self.latest_alerts.push(Alert::new(t.price, "Price crossed over trigger zone!!!"));
+        if let Some(threshold) = self.stale_alert_after {
+            let last_read = self.live_stats_updated().unwrap_or(self.started);
+            let stale_for = last_read.elapsed();
+            if stale_for < threshold {
+                self.stale_alerted = false;
+            } else if !self.stale_alerted {
+                self.stale_alerted = true;
+                metrics::count_alert("stale");
+                self.latest_alerts.push(Alert::stale(stale_for));
             }
         }
     }
 
+    /// Adds the trigger and starts watching its symbol if it isn't watched yet.
+    pub fn add_trigger(&mut self, trigger: PriceTrigger) -> Result<()> {
+        self.check_trigger_currency(&trigger)?;
+        if let Some(symbol) = &trigger.symbol {
+            self.watch(symbol.as_str())?;
+        }
+        self.price_triggers.push(trigger);
+        self.update_rates();
+        Ok(())
+    }
+
+    /// Removes the trigger at `index` returning `None` if there is no such trigger.
+    pub fn remove_trigger(&mut self, index: usize) -> Option<PriceTrigger> {
+        if index < self.price_triggers.len() {
+            Some(self.price_triggers.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Replaces the trigger at `index` returning the replaced trigger or `None` if
+    /// there is no such trigger.
+    pub fn replace_trigger(
+        &mut self,
+        index: usize,
+        trigger: PriceTrigger,
+    ) -> Result<Option<PriceTrigger>> {
+        if index >= self.price_triggers.len() {
+            return Ok(None);
+        }
+        self.check_trigger_currency(&trigger)?;
+        if let Some(symbol) = &trigger.symbol {
+            self.watch(symbol.as_str())?;
+        }
+        let replaced = std::mem::replace(&mut self.price_triggers[index], trigger);
+        self.update_rates();
+        Ok(Some(replaced))
+    }
+
+    /// Returns the errors of the triggers whose prices can't be converted into their
+    /// currency, which are only known once the exchange info is.
+    fn unconvertible_triggers(&self) -> Vec<Error> {
+        self.price_triggers
+            .iter()
+            .filter_map(|trigger| self.check_trigger_currency(trigger).err())
+            .collect()
+    }
+
+    fn check_trigger_currency(&self, trigger: &PriceTrigger) -> Result<()> {
+        match &trigger.currency {
+            Some(currency) => {
+                let symbol = trigger.symbol.as_ref().unwrap_or(&self.symbol);
+                self.check_conversion(symbol, currency)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Enables or disables the trigger at `index` returning `None` if there is no such trigger.
+    pub fn set_trigger_enabled(&mut self, index: usize, enabled: bool) -> Option<PriceTrigger> {
+        let trigger = self.price_triggers.get_mut(index)?;
+        trigger.enabled = enabled;
+        Some(trigger.clone())
+    }
+
+    pub fn triggers(&self) -> &[PriceTrigger] {
+        &self.price_triggers
+    }
+
     /// Increments the inner ticker, updates the `live price stats` and schedules
     /// price analysis for each `TICKS_PER_UPDATE`.
     pub fn update(&mut self) {
-        self.live_stats_tracker.update();
+        let symbol = &self.symbol;
+        let errors = [self.depth_tracker.update(), self.trade_tracker.update()]
+            .into_iter()
+            .flatten()
+            .map(|err| Error::exchange(err, symbol.as_str()))
+            .collect::<Vec<Error>>();
+        let stats_errors = std::iter::once(&mut self.live_stats_tracker)
+            .chain(&mut self.watchlist)
+            .chain(&mut self.rate_trackers)
+            .filter_map(|tracker| {
+                let symbol = tracker.symbol.clone();
+                tracker
+                    .update()
+                    .map(|err| Error::exchange(err, symbol.as_str()))
+            })
+            .collect::<Vec<Error>>();
+        self.errors.extend(errors.into_iter().chain(stats_errors));
+        self.update_exchange_info();
+        self.update_rates();
 
         //self.tick += 1;
         //if self.tick >= Self::TICKS_PER_UPDATE {
-            self.analyze();
-            //self.tick = 0;
+        self.analyze();
+        //self.tick = 0;
         //}
     }
 
-    pub fn alert(&self) -> Vec<Alert> {
-        self.latest_alerts.clone()
+    /// Requests the exchange info when it is missing or stale and reports the
+    /// unknown symbols and the triggers in a currency which can't be converted once
+    /// it is received.
+    fn update_exchange_info(&mut self) {
+        if let Some(reader) = &self.exchange_reader {
+            match reader.try_recv() {
+                Ok(Ok(info)) => {
+                    let unknown = self
+                        .watchlist()
+                        .map(LiveStatsTracker::symbol)
+                        .filter(|symbol| info.get(symbol.as_str()).is_none())
+                        .map(|symbol| Error::invalid_symbol(symbol.as_str()))
+                        .collect::<Vec<Error>>();
+                    self.errors.extend(unknown);
+                    self.exchange = Some(Arc::new(info));
+                    let unconvertible = self.unconvertible_triggers();
+                    self.errors.extend(unconvertible);
+                }
+                Ok(Err(err)) => self.errors.push(err),
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => (),
+            }
+            self.exchange_reader = None;
+            return;
+        }
+
+        let outdated = self.exchange.as_ref().is_none_or(|info| info.is_stale());
+        let retry = self
+            .exchange_requested
+            .is_none_or(|requested| requested.elapsed() >= Self::EXCHANGE_INFO_RETRY);
+        if outdated && retry && self.readers.running.load(Ordering::Relaxed) {
+            self.exchange_requested = Some(Instant::now());
+            self.exchange_reader = Some(exchange::spawn_fetch());
+        }
+    }
+
+    /// Returns the symbols listed on the exchange or `None` if they aren't known yet.
+    pub fn exchange_info(&self) -> Option<Arc<ExchangeInfo>> {
+        self.exchange.clone()
+    }
+
+    /// Shows the prices also in the `currency`, or only in their quote assets if `None`.
+    pub fn set_currency(&mut self, currency: Option<Currency>) -> Result<()> {
+        if let Some(currency) = &currency {
+            self.check_conversion(&self.symbol, currency)?;
+        }
+        self.currency = currency;
+        self.update_rates();
+        Ok(())
+    }
+
+    pub fn currency(&self) -> Option<Currency> {
+        self.currency.clone()
+    }
+
+    /// Returns the asset the prices of the `symbol` are quoted in.
+    fn quote_asset(&self, symbol: &Symbol) -> Option<String> {
+        self.exchange
+            .as_ref()
+            .and_then(|info| info.get(symbol.as_str()))
+            .map(|info| info.quote_asset.clone())
+            .or_else(|| symbol.quote().map(str::to_owned))
+    }
+
+    /// Checks that the prices of the `symbol` can be converted into the `currency`.
+    ///
+    /// Every conversion passes until the exchange info is known.
+    fn check_conversion(&self, symbol: &Symbol, currency: &Currency) -> Result<()> {
+        let Some(info) = &self.exchange else {
+            return Ok(());
+        };
+        let conversion = self
+            .quote_asset(symbol)
+            .and_then(|quote| Conversion::find(info, &quote, currency.asset()));
+        match conversion {
+            Some(_) => Ok(()),
+            None => Err(Error::invalid_currency(currency.as_str()).with_context(symbol.as_str())),
+        }
+    }
+
+    /// Finds the conversions of the watched prices into the display currency and of
+    /// the converted triggers, and starts reading the prices of their pairs.
+    fn update_rates(&mut self) {
+        let Some(info) = self.exchange.clone() else {
+            return;
+        };
+        let displayed = self.currency.iter().flat_map(|currency| {
+            self.watchlist()
+                .map(move |tracker| (tracker.symbol(), currency.clone()))
+        });
+        let converted = self.price_triggers.iter().filter_map(|trigger| {
+            let symbol = trigger.symbol.as_ref().unwrap_or(&self.symbol);
+            Some((symbol.clone(), trigger.currency.clone()?))
+        });
+        let mut conversions = HashMap::new();
+        for (symbol, currency) in displayed.chain(converted).collect::<Vec<_>>() {
+            let Some(quote) = self.quote_asset(&symbol) else {
+                continue;
+            };
+            let key = (quote, currency.asset().to_owned());
+            if conversions.contains_key(&key) {
+                continue;
+            }
+            if let Some(conversion) = Conversion::find(&info, &key.0, &key.1) {
+                conversions.insert(key, conversion);
+            }
+        }
+
+        // The prices of the watched symbols are already read
+        let needed = conversions
+            .values()
+            .flat_map(Conversion::symbols)
+            .filter(|symbol| !self.watchlist().any(|tracker| &tracker.symbol == *symbol))
+            .cloned()
+            .collect::<BTreeSet<Symbol>>();
+        self.rate_trackers
+            .retain(|tracker| needed.contains(&tracker.symbol));
+        for symbol in needed {
+            if !self
+                .rate_trackers
+                .iter()
+                .any(|tracker| tracker.symbol == symbol)
+            {
+                let tracker = LiveStatsTracker::new(self.readers.clone(), symbol);
+                self.rate_trackers.push(tracker);
+            }
+        }
+        self.conversions = conversions;
+    }
+
+    /// Returns the last price of the `symbol` or `None` if it wasn't read yet or
+    /// is stale, so the conversions don't use an old rate.
+    fn price(&self, symbol: &Symbol) -> Option<Decimal> {
+        self.watchlist()
+            .chain(&self.rate_trackers)
+            .find(|tracker| &tracker.symbol == symbol)
+            .filter(|tracker| {
+                tracker
+                    .updated()
+                    .is_some_and(|updated| updated.elapsed() <= ConnectionState::STALE_AFTER)
+            })
+            .map(|tracker| tracker.stats().last_price)
+    }
+
+    /// Returns how much of the `currency` one unit of the quote asset of the `symbol`
+    /// is worth, or `None` while it isn't known.
+    pub fn rate(&self, symbol: &Symbol, currency: &Currency) -> Option<Decimal> {
+        let key = (self.quote_asset(symbol)?, currency.asset().to_owned());
+        self.conversions
+            .get(&key)?
+            .rate(|symbol| self.price(symbol))
+    }
+
+    /// Starts tracking the symbol called `name` returning `false` if it is already tracked.
+    ///
+    /// Symbols which aren't listed on the exchange are rejected once the exchange
+    /// info is known.
+    pub fn watch(&mut self, name: &str) -> Result<bool> {
+        let symbol = Symbol::new(name)?;
+        if let Some(info) = &self.exchange {
+            info.check(symbol.as_str())?;
+        }
+        if self.watchlist().any(|tracker| tracker.symbol == symbol) {
+            return Ok(false);
+        }
+        let tracker = LiveStatsTracker::new(self.readers.clone(), symbol);
+        self.watchlist.push(tracker);
+        Ok(true)
+    }
+
+    /// Stops tracking the symbol called `name` returning `false` if it wasn't tracked.
+    ///
+    /// The main symbol can't be removed from the watchlist, and neither can the
+    /// symbols of the triggers which need their prices.
+    pub fn unwatch(&mut self, name: &str) -> Result<bool> {
+        let name = name.trim();
+        let used = self.price_triggers.iter().position(|trigger| {
+            (trigger.symbol.as_ref())
+                .is_some_and(|symbol| symbol.as_str().eq_ignore_ascii_case(name))
+        });
+        if let Some(index) = used {
+            return Err(Error::command(format!(
+                "{} is used by trigger {}",
+                name.to_uppercase(),
+                index + 1
+            )));
+        }
+        let len = self.watchlist.len();
+        self.watchlist
+            .retain(|tracker| !tracker.symbol.as_str().eq_ignore_ascii_case(name));
+        Ok(self.watchlist.len() != len)
+    }
+
+    /// Returns the trackers of all watched symbols, starting with the main symbol.
+    pub fn watchlist(&self) -> impl Iterator<Item = &LiveStatsTracker> {
+        std::iter::once(&self.live_stats_tracker).chain(&self.watchlist)
+    }
+
+    /// Returns the errors which happened since this was last called.
+    pub fn take_errors(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Returns the main symbol.
+    pub fn symbol(&self) -> Symbol {
+        self.symbol.clone()
+    }
+
+    pub fn alerts(&self) -> &AlertLog {
+        &self.latest_alerts
+    }
+
+    /// Returns when the live stats were last updated or `None` if they never were.
+    pub fn live_stats_updated(&self) -> Option<Instant> {
+        self.live_stats_tracker.updated()
+    }
+
+    /// Returns the order book of the main symbol.
+    pub fn depth(&self) -> Arc<OrderBook> {
+        self.depth_tracker.book()
+    }
+
+    /// Returns when the order book was last updated or `None` if it never was.
+    pub fn depth_updated(&self) -> Option<Instant> {
+        self.depth_tracker.updated()
+    }
+
+    /// Returns the latest trades of the main symbol, the newest being at the back.
+    pub fn trades(&self) -> &VecDeque<Trade> {
+        self.trade_tracker.trades()
+    }
+
+    pub fn large_trade_notional(&self) -> Decimal {
+        self.large_trade_notional
+    }
+
+    pub fn set_large_trade_notional(&mut self, notional: Decimal) {
+        self.large_trade_notional = notional;
+    }
+
+    /// Stops the reader threads waiting at most `timeout` for them to exit.
+    ///
+    /// Threads blocked by a slow request are left behind once the timeout passes.
+    pub fn shutdown(&mut self, timeout: Duration) {
+        self.readers.stop();
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline && !self.readers_stopped() {
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    fn readers_stopped(&self) -> bool {
+        self.watchlist()
+            .chain(&self.rate_trackers)
+            .all(LiveStatsTracker::is_stopped)
+            && connection::is_stopped(&self.depth_tracker.reader)
+            && connection::is_stopped(&self.trade_tracker.reader)
     }
 
-    // TODO maybe do inlining
-    pub fn live_stats(&self) -> Arc<PriceStats> {
-        self.live_stats_tracker.stats()
+    /// Returns the state which is kept between the runs.
+    pub fn save_data(&self) -> SaveData {
+        SaveData {
+            triggers: self.price_triggers.clone(),
+            watchlist: self
+                .watchlist
+                .iter()
+                .map(|tracker| tracker.symbol.clone())
+                .collect(),
+        }
     }
+
+    /// Restores the state saved by a previous run, also watching the symbols of the
+    /// triggers so they can fire.
+    pub fn load(&mut self, data: SaveData) {
+        self.price_triggers = data.triggers;
+        let trigger_symbols = self
+            .price_triggers
+            .iter()
+            .filter_map(|trigger| trigger.symbol.clone())
+            .collect::<Vec<Symbol>>();
+        for symbol in data.watchlist.into_iter().chain(trigger_symbols) {
+            if let Err(err) = self.watch(symbol.as_str()) {
+                self.errors.push(err);
+            }
+        }
+        let unconvertible = self.unconvertible_triggers();
+        self.errors.extend(unconvertible);
+    }
+
+    /// Raises an alert once the prices of the main symbol weren't read for `after`.
+    pub fn set_stale_alert_after(&mut self, after: Option<Duration>) {
+        self.stale_alert_after = after;
+    }
+
+    /// Returns the worst connection state of the main symbol readers.
+    pub fn connection(&self) -> ConnectionState {
+        self.live_stats_tracker
+            .connection()
+            .max(self.depth_tracker.connection())
+            .max(self.trade_tracker.connection())
+    }
+
+    /// Returns everything the TUI shows about the bot.
+    pub fn snapshot(&self) -> BotSnapshot {
+        let age = |updated: Option<Instant>| updated.map(|updated| updated.elapsed());
+        BotSnapshot {
+            symbol: self.symbol(),
+            connection: self.connection(),
+            currency: self.currency(),
+            watchlist: self
+                .watchlist()
+                .map(|tracker| WatchedSymbol {
+                    symbol: tracker.symbol(),
+                    stats: tracker.stats(),
+                    history: tracker.history().iter().copied().collect(),
+                    age: age(tracker.updated),
+                    info: self
+                        .exchange
+                        .as_ref()
+                        .and_then(|info| info.get(tracker.symbol.as_str()))
+                        .cloned(),
+                    rate: self
+                        .currency
+                        .as_ref()
+                        .and_then(|currency| self.rate(&tracker.symbol, currency)),
+                })
+                .collect(),
+            depth: self.depth(),
+            depth_age: age(self.depth_updated()),
+            trades: self.trades().iter().copied().collect(),
+            large_trade_notional: self.large_trade_notional(),
+            triggers: self.price_triggers.clone(),
+            alerts: self.latest_alerts.clone(),
+        }
+    }
+}
+
+/// What the TUI needs from a bot, implemented by the [`Bot`] itself and by the
+/// [`ControlClient`](crate::control::ControlClient) of a bot running in the daemon.
+pub trait BotControl {
+    /// Reads the new market data or the new state of the bot.
+    fn update(&mut self);
+
+    fn snapshot(&self) -> BotSnapshot;
+
+    /// Returns the errors which happened since this was last called.
+    fn take_errors(&mut self) -> Vec<Error>;
+
+    fn add_trigger(&mut self, trigger: PriceTrigger) -> Result<()>;
+
+    /// Removes the trigger at `index` returning `None` if there is no such trigger.
+    fn remove_trigger(&mut self, index: usize) -> Result<Option<PriceTrigger>>;
+
+    /// Enables or disables the trigger at `index` returning `None` if there is no such trigger.
+    fn set_trigger_enabled(&mut self, index: usize, enabled: bool) -> Result<Option<PriceTrigger>>;
+
+    /// Starts tracking the symbol returning `false` if it is already tracked.
+    fn watch(&mut self, name: &str) -> Result<bool>;
+
+    /// Shows the prices also in the `currency`, or only in their quote assets if `None`.
+    fn set_currency(&mut self, currency: Option<Currency>) -> Result<()>;
+
+    /// Stops tracking the symbol returning `false` if it wasn't tracked.
+    fn unwatch(&mut self, name: &str) -> Result<bool>;
+
+    /// Returns the symbols listed on the exchange or `None` if they aren't known yet.
+    fn exchange_info(&self) -> Option<Arc<ExchangeInfo>>;
+
+    /// Returns the state to save or `None` if it is saved elsewhere.
+    fn save_data(&self) -> Option<SaveData>;
+
+    /// Stops the reader threads waiting at most `timeout` for them to exit.
+    fn shutdown(&mut self, timeout: Duration);
+}
+
+impl BotControl for Bot {
+    fn update(&mut self) {
+        Bot::update(self);
+    }
+
+    fn snapshot(&self) -> BotSnapshot {
+        Bot::snapshot(self)
+    }
+
+    fn take_errors(&mut self) -> Vec<Error> {
+        Bot::take_errors(self)
+    }
+
+    fn add_trigger(&mut self, trigger: PriceTrigger) -> Result<()> {
+        Bot::add_trigger(self, trigger)
+    }
+
+    fn remove_trigger(&mut self, index: usize) -> Result<Option<PriceTrigger>> {
+        Ok(Bot::remove_trigger(self, index))
+    }
+
+    fn set_trigger_enabled(&mut self, index: usize, enabled: bool) -> Result<Option<PriceTrigger>> {
+        Ok(Bot::set_trigger_enabled(self, index, enabled))
+    }
+
+    fn watch(&mut self, name: &str) -> Result<bool> {
+        Bot::watch(self, name)
+    }
+
+    fn unwatch(&mut self, name: &str) -> Result<bool> {
+        Bot::unwatch(self, name)
+    }
+
+    fn set_currency(&mut self, currency: Option<Currency>) -> Result<()> {
+        Bot::set_currency(self, currency)
+    }
+
+    fn exchange_info(&self) -> Option<Arc<ExchangeInfo>> {
+        Bot::exchange_info(self)
+    }
+
+    fn save_data(&self) -> Option<SaveData> {
+        Some(Bot::save_data(self))
+    }
+
+    fn shutdown(&mut self, timeout: Duration) {
+        Bot::shutdown(self, timeout);
+    }
+}
+
+/// Everything the TUI shows about a bot at one moment.
+///
+/// When the data was read is given as its age because an [`Instant`] can't be
+/// sent to another process.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct BotSnapshot {
+    pub symbol: Symbol,
+    pub connection: ConnectionState,
+    /// Currency the prices are also shown in.
+    pub currency: Option<Currency>,
+    /// The watched symbols starting with the main symbol.
+    pub watchlist: Vec<WatchedSymbol>,
+    pub depth: Arc<OrderBook>,
+    pub depth_age: Option<Duration>,
+    /// The latest trades of the main symbol, the newest being at the back.
+    pub trades: Vec<Trade>,
+    pub large_trade_notional: Decimal,
+    pub triggers: Vec<PriceTrigger>,
+    pub alerts: AlertLog,
+}
+
+impl BotSnapshot {
+    /// Returns the main symbol.
+    pub fn main(&self) -> &WatchedSymbol {
+        &self.watchlist[0]
+    }
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct WatchedSymbol {
+    pub symbol: Symbol,
+    pub stats: Arc<PriceStats>,
+    /// The last prices, the latest being at the back.
+    pub history: Vec<f64>,
+    /// Time since the stats were read or `None` if they never were.
+    pub age: Option<Duration>,
+    /// Trading rules of the symbol if the exchange info is known.
+    pub info: Option<SymbolInfo>,
+    /// How much of the display currency one unit of the quote asset is worth, or
+    /// `None` if there is no display currency or the rate isn't known yet.
+    pub rate: Option<Decimal>,
+}
+
+/// Converts the age of some data into when it was read, see [`BotSnapshot`].
+pub fn updated_at(age: Option<Duration>) -> Option<Instant> {
+    age.and_then(|age| Instant::now().checked_sub(age))
 }
 
-#[derive(Debug)]
 pub struct LiveStatsTracker {
+    readers: Readers,
+    symbol: Symbol,
     stats: Arc<PriceStats>,
+    /// Last prices read from the market, the latest price being at the back.
+    history: VecDeque<f64>,
+    /// When the stats were last successfully read.
+    updated: Option<Instant>,
+    /// Number of failed reads since the last successful one.
+    failures: u32,
     reader: Receiver<BinanceResult<PriceStats>>,
 }
 
 impl LiveStatsTracker {
-    fn new(market: Arc<Market>, symbol: Symbol) -> Self {
-        let reader = Self::spawn_price_reader(market, symbol);
+    /// Number of the last prices kept in the history.
+    pub const HISTORY_LENGTH: usize = 120;
+
+    pub fn new(readers: Readers, symbol: Symbol) -> Self {
+        let reader = Self::spawn_price_reader(&readers, symbol.clone());
         Self {
+            readers,
+            symbol,
             stats: Arc::new(DEFAULT_PRICE_STATS),
+            history: VecDeque::with_capacity(Self::HISTORY_LENGTH),
+            updated: None,
+            failures: 0,
             reader,
         }
     }
 
-    fn update(&mut self) {
-        if let Some(price) = self.reader.try_iter().last() {
+    /// Stores the latest stats returning the error if reading them failed.
+    pub fn update(&mut self) -> Option<BinanceError> {
+        let (readers, symbol) = (&self.readers, &self.symbol);
+        let results = connection::receive_all(&mut self.reader, || {
+            Self::spawn_price_reader(readers, symbol.clone())
+        });
+        let mut error = None;
+        for price in results {
             match price {
-                Ok(stats) => self.stats = Arc::new(stats),
-                Err(err) => println!("Binance Error: {err}"),
+                Ok(stats) => {
+                    if self.history.len() == Self::HISTORY_LENGTH {
+                        self.history.pop_front();
+                    }
+                    self.history.push_back(stats.last_price.to_f64());
+                    self.stats = Arc::new(stats);
+                    self.updated = Some(Instant::now());
+                    self.failures = 0;
+                }
+                Err(err) => {
+                    self.failures += 1;
+                    metrics::count_fetch_error(&self.symbol, "stats");
+                    error = Some(err);
+                }
             }
         }
+        error
+    }
+
+    pub fn symbol(&self) -> Symbol {
+        self.symbol.clone()
     }
 
-    fn stats(&self) -> Arc<PriceStats> {
+    pub fn stats(&self) -> Arc<PriceStats> {
         self.stats.clone()
     }
 
+    pub fn history(&self) -> &VecDeque<f64> {
+        &self.history
+    }
+
+    pub fn updated(&self) -> Option<Instant> {
+        self.updated
+    }
+
+    pub fn connection(&self) -> ConnectionState {
+        ConnectionState::rate(self.failures, self.updated)
+    }
+
+    fn is_stopped(&self) -> bool {
+        connection::is_stopped(&self.reader)
+    }
+
     /// Reading the price from Binance charts blocks the thread for a short period of time
     /// which can sometimes delay the user input so a new thread is needed.
     ///
-    /// Every [`crate::TICK_INTERVAL`] this thread reads the market price and sends it to
-    /// the main thread which stores it in the next [`crate::TICK_INTERVAL`].
+    /// Every poll interval this thread reads the market price and sends it to the main
+    /// thread which stores it in its next tick. Failed reads are retried less and less
+    /// often, see [`Readers::spawn`].
     ///
-    /// If the price reader thread loses connection with the main thread it will just exit
-    /// and the main thread will probably just spawn a new one.
+    /// If the price reader thread loses connection with the main thread or the readers
+    /// are stopped it will just exit, and if the thread dies the main thread spawns a new
+    /// one in the next update.
+    #[allow(clippy::result_large_err)]
     fn spawn_price_reader(
-        market: Arc<Market>,
+        readers: &Readers,
         symbol: Symbol,
     ) -> Receiver<BinanceResult<PriceStats>> {
+        //market.get_klines(symbol, "1m", None, None, None)
+        readers.spawn(move |market| {
+            let start = Instant::now();
+            let stats = PriceStats::read(market, &symbol);
+            metrics::observe_price_stats_latency(start.elapsed());
+            stats
+        })
+    }
+}
+
+/// Keeps the top of the order book of a symbol.
+pub struct DepthTracker {
+    readers: Readers,
+    symbol: Symbol,
+    book: Arc<OrderBook>,
+    /// When the order book was last successfully read.
+    updated: Option<Instant>,
+    /// Number of failed reads since the last successful one.
+    failures: u32,
+    reader: Receiver<BinanceResult<OrderBook>>,
+}
+
+impl DepthTracker {
+    /// Number of the price levels read on each side of the order book.
+    pub const DEPTH_LIMIT: u64 = 20;
+
+    fn new(readers: Readers, symbol: Symbol) -> Self {
+        let reader = Self::spawn_depth_reader(&readers, symbol.clone());
+        Self {
+            readers,
+            symbol,
+            book: Arc::new(OrderBook {
+                last_update_id: 0,
+                bids: Vec::new(),
+                asks: Vec::new(),
+            }),
+            updated: None,
+            failures: 0,
+            reader,
+        }
+    }
+
+    /// Stores the latest order book returning the error if reading it failed.
+    fn update(&mut self) -> Option<BinanceError> {
+        let (readers, symbol) = (&self.readers, &self.symbol);
+        let results = connection::receive_all(&mut self.reader, || {
+            Self::spawn_depth_reader(readers, symbol.clone())
+        });
+        let mut error = None;
+        for book in results {
+            match book {
+                Ok(book) => {
+                    self.book = Arc::new(book);
+                    self.updated = Some(Instant::now());
+                    self.failures = 0;
+                }
+                Err(err) => {
+                    self.failures += 1;
+                    metrics::count_fetch_error(&self.symbol, "depth");
+                    error = Some(err);
+                }
+            }
+        }
+        error
+    }
+
+    pub fn book(&self) -> Arc<OrderBook> {
+        self.book.clone()
+    }
+
+    pub fn updated(&self) -> Option<Instant> {
+        self.updated
+    }
+
+    pub fn imbalance(&self) -> Option<Decimal> {
+        order_book_imbalance(&self.book)
+    }
+
+    pub fn connection(&self) -> ConnectionState {
+        ConnectionState::rate(self.failures, self.updated)
+    }
+
+    /// Reads the order book in its own thread the same way as
+    /// [`LiveStatsTracker::spawn_price_reader`] reads the price.
+    #[allow(clippy::result_large_err)]
+    fn spawn_depth_reader(readers: &Readers, symbol: Symbol) -> Receiver<BinanceResult<OrderBook>> {
+        readers.spawn(move |market| market.get_custom_depth(symbol.as_str(), Self::DEPTH_LIMIT))
+    }
+}
+
+/// Collects the aggregated trades of a symbol from the Binance trade stream.
+pub struct TradeTracker {
+    symbol: Symbol,
+    readers: Readers,
+    /// The latest trades, the newest being at the back.
+    trades: VecDeque<Trade>,
+    /// Number of trades received in the last update.
+    new_trades: usize,
+    /// Number of failed connections since the last connection or trade.
+    failures: u32,
+    reader: Receiver<BinanceResult<TradeMessage>>,
+}
+
+impl TradeTracker {
+    /// Number of the last trades kept.
+    pub const MAX_TRADES: usize = 200;
+
+    fn new(symbol: Symbol, readers: Readers) -> Self {
+        Self {
+            reader: Self::spawn_trade_reader(symbol.clone(), &readers),
+            symbol,
+            readers,
+            trades: VecDeque::with_capacity(Self::MAX_TRADES),
+            new_trades: 0,
+            failures: 0,
+        }
+    }
+
+    /// Stores the received trades returning the last error of the trade stream.
+    fn update(&mut self) -> Option<BinanceError> {
+        self.new_trades = 0;
+        let (symbol, readers) = (&self.symbol, &self.readers);
+        let results = connection::receive_all(&mut self.reader, || {
+            Self::spawn_trade_reader(symbol.clone(), readers)
+        });
+        let mut error = None;
+        for message in results {
+            match message {
+                Ok(TradeMessage::Connected) => self.failures = 0,
+                Ok(TradeMessage::Trade(trade)) => {
+                    if self.trades.len() == Self::MAX_TRADES {
+                        self.trades.pop_front();
+                    }
+                    self.trades.push_back(trade);
+                    self.new_trades += 1;
+                    self.failures = 0;
+                }
+                Err(err) => {
+                    self.failures += 1;
+                    metrics::count_fetch_error(&self.symbol, "trades");
+                    error = Some(err);
+                }
+            }
+        }
+        self.new_trades = self.new_trades.min(self.trades.len());
+        error
+    }
+
+    pub fn trades(&self) -> &VecDeque<Trade> {
+        &self.trades
+    }
+
+    /// Quiet symbols can go without trades for a long time so only the failed
+    /// connections count.
+    pub fn connection(&self) -> ConnectionState {
+        ConnectionState::rate(self.failures, None)
+    }
+
+    /// Returns the trades received in the last update.
+    pub fn new_trades(&self) -> Vec<Trade> {
+        self.trades
+            .iter()
+            .skip(self.trades.len() - self.new_trades)
+            .copied()
+            .collect()
+    }
+
+    /// Unlike the other readers this thread keeps a WebSocket connection open and
+    /// sends every trade as soon as it arrives.
+    ///
+    /// When the connection is lost the error is sent to the main thread and a new
+    /// connection is made after a [`Backoff`]. Every successful connection is sent too
+    /// so the failures are cleared even when no trade follows. The thread exits once
+    /// the main thread stops receiving or the readers are stopped.
+    // The binance WebSocket handler has to return the binance error type
+    #[allow(clippy::result_large_err)]
+    fn spawn_trade_reader(
+        symbol: Symbol,
+        readers: &Readers,
+    ) -> Receiver<BinanceResult<TradeMessage>> {
+        let running = readers.running.clone();
+        let interval = readers.interval;
         let (tx, rx) = channel();
-        thread::spawn(move || loop {
-            let price = market.get_24h_price_stats(symbol);
-            //market.get_klines(symbol, "1m", None, None, None)
-            match tx.send(price) {
-                Ok(_) => thread::sleep(crate::TICK_INTERVAL),
-                Err(_) => break,
+        let stream = format!("{}@aggTrade", symbol.as_str().to_lowercase());
+        thread::spawn(move || {
+            let mut backoff = Backoff::new(interval);
+            while running.load(Ordering::Relaxed) {
+                let trades = tx.clone();
+                let mut socket = WebSockets::new(move |event| {
+                    if let WebsocketEvent::AggrTrades(event) = event {
+                        trades
+                            .send(Ok(TradeMessage::Trade(Trade::from(&event))))
+                            .map_err(|_| "trade receiver was dropped")?;
+                    }
+                    Ok(())
+                });
+                let result = socket.connect(&stream).and_then(|_| {
+                    backoff.reset();
+                    tx.send(Ok(TradeMessage::Connected))
+                        .map_err(|_| "trade receiver was dropped")?;
+                    socket.event_loop(&running)
+                });
+                if let Err(err) = result {
+                    if tx.send(Err(err)).is_err() {
+                        break;
+                    }
+                }
+                connection::sleep_while_running(backoff.next_delay(), &running);
             }
         });
         rx
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct Symbol(pub &'static str);
+/// Message sent by the trade stream thread.
+enum TradeMessage {
+    /// The WebSocket connection was made.
+    Connected,
+    Trade(Trade),
+}
+
+/// A single aggregated trade.
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct Trade {
+    pub price: Decimal,
+    pub qty: Decimal,
+    /// Trade time in milliseconds since the Unix epoch.
+    pub time: u64,
+    /// Whether the buyer placed the resting order which makes the trade a sell.
+    pub buyer_maker: bool,
+}
+
+impl Trade {
+    /// Value of the trade in the quote asset or `None` if it can't be computed.
+    pub fn notional(&self) -> Option<Decimal> {
+        self.price.checked_mul(self.qty)
+    }
+}
 
-impl From<&'static str> for Symbol {
-    fn from(str: &'static str) -> Symbol {
-        Symbol(str)
+impl From<&AggrTradesEvent> for Trade {
+    fn from(event: &AggrTradesEvent) -> Self {
+        Self {
+            price: event.price.parse().unwrap_or_default(),
+            qty: event.qty.parse().unwrap_or_default(),
+            time: event.trade_order_time,
+            buyer_maker: event.is_buyer_maker,
+        }
     }
 }
 
-impl From<Symbol> for String {
-    fn from(symbol: Symbol) -> String {
-        symbol.0.to_owned()
+/// Returns the difference between the bid and ask quantities in percent of their
+/// sum with [`Bot::IMBALANCE_SCALE`] decimals, from `-100` (only asks) to `100` (only
+/// bids), or `None` if the order book is empty.
+pub fn order_book_imbalance(book: &OrderBook) -> Option<Decimal> {
+    let bids = book.bids.iter().try_fold(Decimal::ZERO, |sum, bid| {
+        sum.checked_add(Decimal::from_f64(bid.qty)?)
+    })?;
+    let asks = book.asks.iter().try_fold(Decimal::ZERO, |sum, ask| {
+        sum.checked_add(Decimal::from_f64(ask.qty)?)
+    })?;
+    let total = bids.checked_add(asks)?;
+    bids.checked_sub(asks)?
+        .checked_mul(Decimal::new(100, 0))?
+        .checked_div_rounded(total, Bot::IMBALANCE_SCALE)
+}
+
+/// The latest alerts, the oldest ones being dropped after [`AlertLog::CAPACITY`].
+///
+/// Every alert raised gets the next id so the new alerts can be told apart from
+/// the ones already seen even after the old ones are dropped.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct AlertLog {
+    /// The kept alerts, the newest being at the back.
+    alerts: VecDeque<Alert>,
+    /// Number of alerts raised, which is the id of the next alert.
+    raised: usize,
+}
+
+impl AlertLog {
+    /// Number of the latest alerts kept.
+    pub const CAPACITY: usize = 100;
+
+    fn push(&mut self, alert: Alert) {
+        if self.alerts.len() == Self::CAPACITY {
+            self.alerts.pop_front();
+        }
+        self.alerts.push_back(alert);
+        self.raised += 1;
+    }
+
+    /// Returns the number of alerts raised, including the dropped ones.
+    pub fn raised(&self) -> usize {
+        self.raised
+    }
+
+    pub fn len(&self) -> usize {
+        self.alerts.len()
+    }
+
+    /// Returns the kept alerts with their ids, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Alert)> {
+        let first = self.raised - self.alerts.len();
+        (first..).zip(&self.alerts)
+    }
+
+    /// Returns the alert with the `id` or `None` if it was dropped or not raised yet.
+    pub fn get(&self, id: usize) -> Option<&Alert> {
+        let first = self.raised - self.alerts.len();
+        self.alerts.get(id.checked_sub(first)?)
+    }
+
+    /// Returns the kept alerts raised after the first `seen` ones.
+    pub fn since(&self, seen: usize) -> impl Iterator<Item = &Alert> {
+        self.iter()
+            .filter(move |&(id, _)| id >= seen)
+            .map(|(_, alert)| alert)
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Alert {
-    pub price: PriceLevel,
-    pub message: &'static str,
+    pub kind: AlertKind,
+    /// Symbol of the fired trigger or `None` for the main symbol.
+    pub symbol: Option<Symbol>,
+    /// Currency the level of the fired trigger is in or `None` for the quote asset.
+    #[serde(default)]
+    pub currency: Option<Currency>,
+    pub delivery: Delivery,
+    pub time: SystemTime,
+}
+
+impl Alert {
+    fn new(trigger: &PriceTrigger) -> Self {
+        Self {
+            kind: AlertKind::Trigger {
+                price: trigger.price,
+                target: trigger.target,
+            },
+            symbol: trigger.symbol.clone(),
+            currency: trigger.currency.clone(),
+            delivery: trigger.delivery,
+            time: SystemTime::now(),
+        }
+    }
+
+    /// Alert about the prices of the main symbol not being read for `duration`.
+    fn stale(duration: Duration) -> Self {
+        Self {
+            kind: AlertKind::Stale(duration),
+            symbol: None,
+            currency: None,
+            delivery: Delivery::Bell,
+            time: SystemTime::now(),
+        }
+    }
 }
 
 impl Alert {
-    fn new(price: PriceLevel, message: &'static str) -> Self {
-        Self { price, message }
+    /// Describes the alert like its [`Display`] but with the level in the `precision`
+    /// of the prices of its symbol, see [`TriggerTarget::format_level`].
+    pub fn describe(&self, precision: Option<Precision>) -> String {
+        match self.kind {
+            AlertKind::Trigger { price, target } => {
+                let symbol = match &self.symbol {
+                    Some(symbol) => format!("{symbol} "),
+                    None => String::new(),
+                };
+                let precision = level_precision(precision, self.currency.as_ref());
+                let level = target.format_level(price, precision);
+                match (target, &self.currency) {
+                    (TriggerTarget::Price, Some(currency)) => {
+                        format!("Alert at {symbol}{level} {currency}")
+                    }
+                    (TriggerTarget::Price, None) => format!("Alert at {symbol}{level}$"),
+                    (target, _) => format!("Alert at {symbol}{target} {level}"),
+                }
+            }
+            AlertKind::Stale(duration) => format!("No prices for {}s", duration.as_secs()),
+        }
     }
 }
 
-#[derive(Debug)]
-struct PriceTrigger {
-    price: PriceLevel,
-    condition: TriggerCondition,
+impl Display for Alert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.describe(None))
+    }
 }
 
-#[derive(Debug)]
-enum TriggerCondition {
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    /// A trigger fired at its level.
+    Trigger {
+        price: PriceLevel,
+        target: TriggerTarget,
+    },
+    /// The prices weren't read for this long.
+    Stale(Duration),
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PriceTrigger {
+    pub price: PriceLevel,
+    #[serde(default)]
+    pub target: TriggerTarget,
+    pub condition: TriggerCondition,
+    /// Symbol whose price is compared or `None` for the main symbol.
+    ///
+    /// Only price triggers can be set on the other symbols.
+    #[serde(default)]
+    pub symbol: Option<Symbol>,
+    /// Currency the price is converted into before it is compared, or `None` to
+    /// compare the price in the quote asset of the symbol.
+    #[serde(default)]
+    pub currency: Option<Currency>,
+    #[serde(default)]
+    pub delivery: Delivery,
+    /// Disabled triggers are skipped while analyzing the price.
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    /// Whether the trigger fired and its condition still holds.
+    #[serde(skip)]
+    fired: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl PriceTrigger {
+    /// Imbalance levels are percents between minus this and this.
+    const MAX_IMBALANCE: Decimal = Decimal::new(100, 0);
+
+    /// Records whether the condition `holds`, keeping the last state while it isn't
+    /// known, and returns whether the trigger fires because the condition started to hold.
+    fn fires(&mut self, holds: Option<bool>) -> bool {
+        if !self.enabled {
+            self.fired = false;
+            return false;
+        }
+        let Some(holds) = holds else {
+            return false;
+        };
+        let crossed = holds && !self.fired;
+        self.fired = holds;
+        crossed
+    }
+
+    /// Checks that the level makes sense for the target and that only price
+    /// triggers are set on the other symbols.
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        match self.target {
+            TriggerTarget::Imbalance
+                if !(-Self::MAX_IMBALANCE..=Self::MAX_IMBALANCE).contains(&self.price.0) =>
+            {
+                Err("the imbalance must be between -100% and 100%".to_owned())
+            }
+            TriggerTarget::Trade if self.condition == TriggerCondition::LowerEq => {
+                Err("trade triggers can only fire above a value".to_owned())
+            }
+            TriggerTarget::Imbalance | TriggerTarget::Trade if self.symbol.is_some() => Err(
+                format!("{} triggers only work on the main symbol", self.target),
+            ),
+            TriggerTarget::Imbalance | TriggerTarget::Trade if self.currency.is_some() => {
+                Err(format!(
+                    "{} triggers can't be converted into a currency",
+                    self.target
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn with_target(
+        price: PriceLevel,
+        target: TriggerTarget,
+        condition: TriggerCondition,
+    ) -> Self {
+        Self {
+            price,
+            target,
+            condition,
+            symbol: None,
+            currency: None,
+            delivery: Delivery::default(),
+            enabled: true,
+            fired: false,
+        }
+    }
+}
+
+impl PriceTrigger {
+    /// Describes the trigger like its [`Display`] but with the level in the `precision`
+    /// of the prices of its symbol, see [`TriggerTarget::format_level`].
+    pub fn describe(&self, precision: Option<Precision>) -> String {
+        let mut text = match &self.symbol {
+            Some(symbol) => format!("{symbol} "),
+            None => String::new(),
+        };
+        let precision = level_precision(precision, self.currency.as_ref());
+        text += &format!(
+            "{} {} {}",
+            self.target,
+            self.condition,
+            self.target.format_level(self.price, precision)
+        );
+        if let Some(currency) = &self.currency {
+            text += &format!(" {currency}");
+        }
+        match self.delivery {
+            Delivery::Tui => text,
+            delivery => format!("{text} ({delivery})"),
+        }
+    }
+}
+
+impl Display for PriceTrigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.describe(None))
+    }
+}
+
+/// Returns the precision of a level which is converted into the `currency`, or the
+/// `precision` of the prices of its symbol if it isn't converted.
+fn level_precision(precision: Option<Precision>, currency: Option<&Currency>) -> Option<Precision> {
+    match currency {
+        Some(currency) => precision.map(|_| currency.precision()),
+        None => precision,
+    }
+}
+
+/// How the user is notified about the alerts of a trigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Delivery {
+    /// Only shown in the TUI.
+    #[default]
+    Tui,
+    /// Shown in the TUI and the terminal bell is rung.
+    Bell,
+}
+
+impl Delivery {
+    pub const ALL: &[Delivery] = &[Delivery::Tui, Delivery::Bell];
+}
+
+impl Display for Delivery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Delivery::Tui => write!(f, "tui"),
+            Delivery::Bell => write!(f, "bell"),
+        }
+    }
+}
+
+impl FromStr for Delivery {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|delivery| delivery.to_string() == s)
+            .ok_or_else(|| format!("unknown delivery '{s}'"))
+    }
+}
+
+/// Value of the main symbol which is compared with the trigger level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TriggerTarget {
+    /// The last price.
+    #[default]
+    Price,
+    /// The order book imbalance in percent, see [`order_book_imbalance`].
+    Imbalance,
+    /// The notional value of a single trade.
+    Trade,
+}
+
+impl TriggerTarget {
+    /// Name of the target in the save file and the APIs, e.g. `price`.
+    pub fn name(self) -> &'static str {
+        match self {
+            TriggerTarget::Price => "price",
+            TriggerTarget::Imbalance => "imbalance",
+            TriggerTarget::Trade => "trade",
+        }
+    }
+
+    /// Formats the level exactly as it was set, or with thousands separators and
+    /// the `precision` of the prices of the symbol if it is given.
+    ///
+    /// Levels set more precisely than the prices keep all their decimals so they
+    /// aren't shown rounded.
+    pub fn format_level(self, level: PriceLevel, precision: Option<Precision>) -> String {
+        match (self, precision) {
+            (TriggerTarget::Imbalance, _) => format!("{}%", level.0),
+            (TriggerTarget::Price, Some(precision)) => precision
+                .at_least(level.0.scale() as usize)
+                .format(level.0.to_f64()),
+            // Notional values are in the quote asset whose precision isn't known
+            (TriggerTarget::Trade, Some(_)) => Precision::default().format(level.0.to_f64()),
+            (_, None) => level.0.to_string(),
+        }
+    }
+}
+
+impl Display for TriggerTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TriggerTarget::Price => write!(f, "Price"),
+            TriggerTarget::Imbalance => write!(f, "Imbalance"),
+            TriggerTarget::Trade => write!(f, "Trade"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerCondition {
     HigherEq,
     LowerEq,
 }
 
+impl Display for TriggerCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TriggerCondition::HigherEq => write!(f, ">="),
+            TriggerCondition::LowerEq => write!(f, "<="),
+        }
+    }
+}
+
 /// Represents a single price level.
-#[derive(Debug, Clone, Copy)]
-pub struct PriceLevel(pub f64);
+///
+/// The level is kept as a [`Decimal`] so it is saved and shown exactly as it was typed.
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct PriceLevel(pub Decimal);
 
 impl Display for PriceLevel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -204,22 +1497,188 @@ impl Display for PriceLevel {
     }
 }
 
+/// Price change statistics of a symbol in the last 24 hours.
+///
+/// Binance sends the prices as strings which are read into [`Decimal`]s so no
+/// digits are lost, unlike with [`binance::model::PriceStats`].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceStats {
+    pub symbol: String,
+    pub price_change: Decimal,
+    pub price_change_percent: Decimal,
+    pub weighted_avg_price: Decimal,
+    pub last_price: Decimal,
+    pub bid_price: Decimal,
+    pub ask_price: Decimal,
+    pub open_price: Decimal,
+    pub high_price: Decimal,
+    pub low_price: Decimal,
+    /// Traded volume in the base asset.
+    pub volume: Decimal,
+    /// Traded volume in the quote asset.
+    pub quote_volume: Decimal,
+    pub open_time: u64,
+    pub close_time: u64,
+    /// Number of the trades.
+    pub count: u64,
+}
+
+impl PriceStats {
+    /// Reads the 24h statistics of the `symbol` from the `market`.
+    #[allow(clippy::result_large_err)]
+    pub fn read(market: &Market, symbol: &Symbol) -> BinanceResult<Self> {
+        let request = format!("symbol={symbol}");
+        market
+            .client
+            .get(API::Spot(Spot::Ticker24hr), Some(request))
+    }
+}
+
 pub const DEFAULT_PRICE_STATS: PriceStats = PriceStats {
     symbol: String::new(),
-    price_change: String::new(),
-    price_change_percent: String::new(),
-    weighted_avg_price: String::new(),
-    prev_close_price: 0.0,
-    last_price: 0.0,
-    bid_price: 0.0,
-    ask_price: 0.0,
-    open_price: 0.0,
-    high_price: 0.0,
-    low_price: 0.0,
-    volume: 0.0,
+    price_change: Decimal::ZERO,
+    price_change_percent: Decimal::ZERO,
+    weighted_avg_price: Decimal::ZERO,
+    last_price: Decimal::ZERO,
+    bid_price: Decimal::ZERO,
+    ask_price: Decimal::ZERO,
+    open_price: Decimal::ZERO,
+    high_price: Decimal::ZERO,
+    low_price: Decimal::ZERO,
+    volume: Decimal::ZERO,
+    quote_volume: Decimal::ZERO,
     open_time: 0,
     close_time: 0,
-    first_id: 0,
-    last_id: 0,
     count: 0,
 };
+
+#[test]
+fn order_book_imbalance_range() {
+    use binance::model::{Asks, Bids};
+
+    let mut book = OrderBook {
+        last_update_id: 0,
+        bids: vec![Bids::new(99.0, 3.0)],
+        asks: vec![Asks {
+            price: 101.0,
+            qty: 1.0,
+        }],
+    };
+    assert_eq!(order_book_imbalance(&book), Some(Decimal::new(50, 0)));
+    book.asks[0].qty = 2.0;
+    // 1/5 is exactly 20 percent while -1/3 is rounded once
+    assert_eq!(order_book_imbalance(&book), Some(Decimal::new(20, 0)));
+    book.bids[0].qty = 1.0;
+    assert_eq!(order_book_imbalance(&book), Some(Decimal::new(-333333, 4)));
+    book.bids.clear();
+    assert_eq!(order_book_imbalance(&book), Some(Decimal::new(-100, 0)));
+    book.asks.clear();
+    assert_eq!(order_book_imbalance(&book), None);
+}
+
+#[test]
+fn price_stats_from_binance() {
+    let response = r#"{"symbol": "ETHUSDT", "priceChange": "-14.69000000",
+        "priceChangePercent": "-1.205", "weightedAvgPrice": "1212.96351412",
+        "prevClosePrice": "1219.07000000", "lastPrice": "1204.38000000",
+        "lastQty": "0.05000000", "bidPrice": "1204.37000000", "bidQty": "12.3",
+        "askPrice": "1204.38000000", "askQty": "0.5", "openPrice": "1219.07000000",
+        "highPrice": "1228.00000000", "lowPrice": "1195.50000000",
+        "volume": "381924.45030000", "quoteVolume": "463257013.13718600",
+        "openTime": 1666000000000, "closeTime": 1666086399999, "firstId": 1,
+        "lastId": 1000, "count": 1000}"#;
+    let stats: PriceStats = serde_json::from_str(response).unwrap();
+    assert_eq!(stats.last_price, Decimal::new(120438, 2));
+    assert_eq!(stats.price_change_percent, Decimal::new(-1205, 3));
+    assert_eq!(stats.quote_volume.to_string(), "463257013.137186");
+
+    // The level is reached exactly, without the rounding of f64
+    let level = PriceLevel("1204.380".parse().unwrap());
+    assert_eq!(stats.last_price, level.0);
+    assert!(stats.bid_price < level.0);
+    let tick = Some(Precision::from_step("0.01"));
+    assert_eq!(TriggerTarget::Price.format_level(level, tick), "1,204.38");
+    let typed = PriceLevel("1209.375".parse().unwrap());
+    assert_eq!(TriggerTarget::Price.format_level(typed, tick), "1,209.375");
+    let trade = Trade::from(&AggrTradesEvent {
+        event_type: "aggTrade".to_owned(),
+        event_time: 0,
+        symbol: "ETHUSDT".to_owned(),
+        aggregated_trade_id: 0,
+        price: "0.1".to_owned(),
+        qty: "3".to_owned(),
+        first_break_trade_id: 0,
+        last_break_trade_id: 0,
+        trade_order_time: 0,
+        is_buyer_maker: false,
+        m_ignore: false,
+    });
+    assert_eq!(trade.notional(), "0.3".parse().ok());
+}
+
+#[test]
+fn triggers_fire_on_crossing() {
+    let mut trigger = PriceTrigger::with_target(
+        PriceLevel(Decimal::new(1210, 0)),
+        TriggerTarget::Price,
+        TriggerCondition::HigherEq,
+    );
+    let fired = [
+        Some(false),
+        Some(true),
+        Some(true),
+        None,
+        Some(false),
+        Some(true),
+    ]
+    .map(|holds| trigger.fires(holds));
+    assert_eq!(fired, [false, true, false, false, false, true]);
+    // Disabling the trigger re-arms it
+    trigger.enabled = false;
+    assert!(!trigger.fires(Some(true)));
+    trigger.enabled = true;
+    assert!(trigger.fires(Some(true)));
+
+    let mut log = AlertLog::default();
+    for _ in 0..AlertLog::CAPACITY + 5 {
+        log.push(Alert::stale(Duration::from_secs(10)));
+    }
+    assert_eq!(log.len(), AlertLog::CAPACITY);
+    assert_eq!(log.raised(), AlertLog::CAPACITY + 5);
+    assert!(log.get(4).is_none());
+    assert!(log.get(5).is_some());
+    assert_eq!(log.since(AlertLog::CAPACITY + 3).count(), 2);
+    assert_eq!(log.since(0).count(), AlertLog::CAPACITY);
+}
+
+#[test]
+fn trigger_symbols_stay_watched() {
+    let mut bot =
+        Bot::with_symbol(Symbol::new("ETHUSDT").unwrap(), Duration::from_secs(60)).unwrap();
+    // Nothing is read from the exchange
+    bot.readers.stop();
+    bot.exchange = None;
+
+    let mut trigger = PriceTrigger::with_target(
+        PriceLevel(Decimal::new(20000, 0)),
+        TriggerTarget::Price,
+        TriggerCondition::LowerEq,
+    );
+    trigger.symbol = Some(Symbol::new("BTCUSDT").unwrap());
+    bot.load(SaveData {
+        triggers: vec![trigger],
+        watchlist: vec![Symbol::new("BNBUSDT").unwrap()],
+    });
+    let watched = bot.watchlist().map(|tracker| tracker.symbol().to_string());
+    assert_eq!(
+        watched.collect::<Vec<String>>(),
+        ["ETHUSDT", "BNBUSDT", "BTCUSDT"]
+    );
+
+    assert!(bot.unwatch("btcusdt").is_err());
+    assert!(bot.unwatch("BNBUSDT").unwrap());
+    bot.remove_trigger(0);
+    assert!(bot.unwatch("btcusdt").unwrap());
+    bot.shutdown(Duration::from_secs(1));
+}
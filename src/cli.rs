@@ -0,0 +1,436 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use binance::{api::Binance, market::Market};
+
+use crate::{
+    bot::{LiveStatsTracker, PriceStats, PriceTrigger},
+    command::Command,
+    config::{self, Config},
+    connection::Readers,
+    error::{Error, Result},
+    exchange,
+    save::{self, SaveData},
+    signals::ShutdownSignal,
+    sinks,
+    symbol::Symbol,
+};
+
+/// What the bot was asked to do on the command line.
+#[derive(Debug)]
+pub enum CliCommand {
+    /// Runs the terminal UI.
+    Tui,
+    /// Evaluates the triggers without a terminal, see [`crate::daemon::run`].
+    Daemon,
+    /// Runs the terminal UI showing the bot of the running daemon.
+    Attach,
+    /// Prints the prices of the symbols whenever they are read.
+    Watch(Vec<String>),
+    /// Edits the saved triggers.
+    Triggers(TriggersCommand),
+    /// Prints the current price of the symbol.
+    Price(String),
+    /// Lists the symbols of the exchange containing the text, or all of them.
+    Symbols(Option<String>),
+    /// Writes the saved state to the file or to the standard output.
+    Export(Option<PathBuf>),
+    /// Replaces the saved state with the file.
+    Import(PathBuf),
+    /// Validates and prints the effective config.
+    ConfigCheck,
+    Help,
+}
+
+#[derive(Debug)]
+pub enum TriggersCommand {
+    Add(PriceTrigger),
+    /// Removes the trigger at the index.
+    Remove(usize),
+    List,
+}
+
+/// Parsed command line arguments.
+#[derive(Debug)]
+pub struct Cli {
+    pub command: CliCommand,
+    /// Config file given by `--config`.
+    pub config: Option<PathBuf>,
+    /// Config values given by the flags as `(key, value)`.
+    pub overrides: Vec<(String, String)>,
+}
+
+impl Cli {
+    /// Parses the arguments without the program name.
+    ///
+    /// Flags take their value either as the next argument or after `=`.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        let mut args = args.into_iter();
+        let mut positional = Vec::new();
+        let mut config = None;
+        let mut overrides = Vec::new();
+        let mut help = false;
+
+        while let Some(arg) = args.next() {
+            if arg == "-h" || arg == "--help" {
+                help = true;
+                continue;
+            }
+            if !arg.starts_with("--") {
+                positional.push(arg);
+                continue;
+            }
+
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_owned(), Some(value.to_owned())),
+                None => (arg, None),
+            };
+            let key = match flag.as_str() {
+                "--config" => None,
+                _ => Some(
+                    Config::keys()
+                        .find(|key| config::flag_name(key) == flag)
+                        .ok_or_else(|| Error::usage(format!("unknown flag '{flag}'")))?,
+                ),
+            };
+            let value = inline_value
+                .or_else(|| args.next())
+                .ok_or_else(|| Error::usage(format!("missing value for '{flag}'")))?;
+            match key {
+                Some(key) => overrides.push((key, value)),
+                None => config = Some(PathBuf::from(value)),
+            }
+        }
+
+        let positional = positional.iter().map(String::as_str).collect::<Vec<&str>>();
+        let command = match positional[..] {
+            _ if help => CliCommand::Help,
+            [] | ["tui"] => CliCommand::Tui,
+            ["daemon"] => CliCommand::Daemon,
+            ["attach"] => CliCommand::Attach,
+            ["watch", ref symbols @ ..] if !symbols.is_empty() => {
+                CliCommand::Watch(symbols.iter().map(|s| s.to_uppercase()).collect())
+            }
+            ["triggers", "add", ref words @ ..] => {
+                CliCommand::Triggers(TriggersCommand::Add(parse_trigger(words)?))
+            }
+            ["triggers", "rm", number] => match parse_command(&format!("rm {number}"))? {
+                Command::RemoveTrigger(index) => {
+                    CliCommand::Triggers(TriggersCommand::Remove(index))
+                }
+                _ => unreachable!("'rm' always parses as removing a trigger"),
+            },
+            ["triggers"] | ["triggers", "list"] => CliCommand::Triggers(TriggersCommand::List),
+            ["price", symbol] => CliCommand::Price(symbol.to_uppercase()),
+            ["symbols"] => CliCommand::Symbols(None),
+            ["symbols", query] => CliCommand::Symbols(Some(query.to_owned())),
+            ["export"] => CliCommand::Export(None),
+            ["export", path] => CliCommand::Export(Some(PathBuf::from(path))),
+            ["import", path] => CliCommand::Import(PathBuf::from(path)),
+            ["config", "check"] => CliCommand::ConfigCheck,
+            _ => {
+                let message = format!("unknown command '{}', see --help", positional.join(" "));
+                return Err(Error::usage(message));
+            }
+        };
+        Ok(Self {
+            command,
+            config,
+            overrides,
+        })
+    }
+}
+
+/// Parses the words of an Input Box command, see [`Command`].
+fn parse_command(line: &str) -> Result<Command> {
+    line.parse()
+        .map_err(|err| Error::usage(format!("{err} in '{line}'")))
+}
+
+/// Parses the arguments of `triggers add` which are the same as of the `add`
+/// command, optionally preceded by the symbol of the trigger.
+fn parse_trigger(words: &[&str]) -> Result<PriceTrigger> {
+    let parse = |words: &[&str]| match parse_command(&format!("add {}", words.join(" ")))? {
+        Command::AddTrigger(trigger) => Ok(trigger),
+        _ => Err(Error::usage("missing condition in 'triggers add'")),
+    };
+    match (parse(words), words) {
+        (Ok(trigger), _) => Ok(trigger),
+        // Symbols are never words of the `add` command so the first word is
+        // only taken as the symbol when the rest parses without it
+        (Err(err), [symbol, rest @ ..]) => {
+            let mut trigger = parse(rest).map_err(|_| err)?;
+            trigger.symbol = Some(Symbol::new(symbol)?);
+            Ok(trigger)
+        }
+        (Err(err), []) => Err(err),
+    }
+}
+
+/// Runs a command which doesn't open the TUI.
+pub fn run(command: CliCommand, config: &Config) -> Result<()> {
+    match command {
+        CliCommand::Watch(symbols) => watch(&symbols, config),
+        CliCommand::Triggers(command) => edit_triggers(command, config),
+        CliCommand::Price(symbol) => {
+            let symbol = Symbol::new(&symbol)?;
+            exchange::check_cached([symbol.as_str()])?;
+            let stats = PriceStats::read(&Market::new(None, None), &symbol)
+                .map_err(|err| Error::exchange(err, symbol.as_str()))?;
+            println!(
+                "{symbol} {} ({}%)",
+                stats.last_price, stats.price_change_percent
+            );
+            Ok(())
+        }
+        CliCommand::Symbols(query) => {
+            let info = exchange::load()?;
+            let found = info.search(query.as_deref().unwrap_or_default());
+            let mut stdout = io::stdout().lock();
+            writeln!(
+                stdout,
+                "{:<16}{:<18}{:<14}{:<14}STATUS",
+                "SYMBOL", "PAIR", "TICK", "LOT"
+            )?;
+            for symbol in &found {
+                writeln!(
+                    stdout,
+                    "{:<16}{:<18}{:<14}{:<14}{}",
+                    symbol.symbol,
+                    symbol.pair(),
+                    symbol.tick_size.as_deref().unwrap_or("-"),
+                    symbol.lot_size.as_deref().unwrap_or("-"),
+                    symbol.status
+                )?;
+            }
+            Ok(())
+        }
+        CliCommand::Export(path) => {
+            let data = save::load_save_file()?.unwrap_or_default();
+            let json = serde_json::to_string_pretty(&data)?;
+            match path {
+                Some(path) => fs::write(path, json)?,
+                None => println!("{json}"),
+            }
+            Ok(())
+        }
+        CliCommand::Import(path) => {
+            let data: SaveData = serde_json::from_slice(&fs::read(&path)?)?;
+            for trigger in &data.triggers {
+                trigger
+                    .validate()
+                    .map_err(|err| Error::config(format!("trigger {trigger}: {err}")))?;
+            }
+            let symbols = data
+                .triggers
+                .iter()
+                .filter_map(|trigger| trigger.symbol.as_ref());
+            exchange::check_cached(data.watchlist.iter().chain(symbols).map(Symbol::as_str))?;
+            save::schedule(&data)?;
+            save::flush()?;
+            println!(
+                "Imported {} triggers and {} watched symbols",
+                data.triggers.len(),
+                data.watchlist.len()
+            );
+            Ok(())
+        }
+        CliCommand::Tui
+        | CliCommand::Daemon
+        | CliCommand::Attach
+        | CliCommand::ConfigCheck
+        | CliCommand::Help => {
+            unreachable!("{command:?} is run by main")
+        }
+    }
+}
+
+/// Prints a line with the price of every symbol whenever it is read until a
+/// shutdown signal is received.
+fn watch(symbols: &[String], config: &Config) -> Result<()> {
+    const STEP: Duration = Duration::from_millis(100);
+
+    let shutdown_signal = ShutdownSignal::register()?;
+    let readers = Readers::new(config.poll_interval());
+    let symbols = symbols
+        .iter()
+        .map(|symbol| Symbol::new(symbol))
+        .collect::<Result<Vec<Symbol>>>()?;
+    exchange::check_cached(symbols.iter().map(Symbol::as_str))?;
+    let mut trackers = symbols
+        .into_iter()
+        .map(|symbol| LiveStatsTracker::new(readers.clone(), symbol))
+        .collect::<Vec<LiveStatsTracker>>();
+    let mut printed: Vec<Option<Instant>> = vec![None; trackers.len()];
+
+    let mut stdout = io::stdout().lock();
+    while !shutdown_signal.received() {
+        for (tracker, printed) in trackers.iter_mut().zip(&mut printed) {
+            if let Some(err) = tracker.update() {
+                eprintln!("{}", Error::exchange(err, tracker.symbol().as_str()));
+            }
+            if tracker.updated() != *printed {
+                *printed = tracker.updated();
+                let stats = tracker.stats();
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                writeln!(
+                    stdout,
+                    "{} {} {} {}%",
+                    sinks::format_timestamp(now.as_secs()),
+                    tracker.symbol(),
+                    stats.last_price,
+                    stats.price_change_percent
+                )?;
+            }
+        }
+        stdout.flush()?;
+        thread::sleep(STEP);
+    }
+    readers.stop();
+    Ok(())
+}
+
+/// Edits the triggers in the save file.
+fn edit_triggers(command: TriggersCommand, config: &Config) -> Result<()> {
+    let mut data = save::load_save_file()?.unwrap_or_default();
+    let no_trigger = |index: usize| Error::command(format!("no trigger {}", index + 1));
+
+    match command {
+        TriggersCommand::Add(mut trigger) => {
+            // Triggers of the main symbol follow it when it is changed
+            if trigger
+                .symbol
+                .as_ref()
+                .is_some_and(|symbol| symbol.as_str() == config.symbol())
+            {
+                trigger.symbol = None;
+            }
+            trigger.validate().map_err(Error::command)?;
+            if let Some(symbol) = &trigger.symbol {
+                exchange::check_cached([symbol.as_str()])?;
+                if !data.watchlist.contains(symbol) {
+                    data.watchlist.push(symbol.clone());
+                }
+            }
+            println!("Added trigger {}: {trigger}", data.triggers.len() + 1);
+            data.triggers.push(trigger);
+        }
+        TriggersCommand::Remove(index) => {
+            if index >= data.triggers.len() {
+                return Err(no_trigger(index));
+            }
+            let trigger = data.triggers.remove(index);
+            println!("Removed trigger {}: {trigger}", index + 1);
+        }
+        TriggersCommand::List => {
+            if data.triggers.is_empty() {
+                println!("No triggers");
+            }
+            for (index, trigger) in data.triggers.iter().enumerate() {
+                let disabled = if trigger.enabled { "" } else { " (disabled)" };
+                println!("{}. {trigger}{disabled}", index + 1);
+            }
+            return Ok(());
+        }
+    }
+    save::schedule(&data)?;
+    save::flush()
+}
+
+/// Returns the help printed by `--help`.
+pub fn usage() -> String {
+    let mut usage = String::from(
+        "Usage: crypto-alertabot [COMMAND] [FLAGS]\n\
+        \n\
+        Commands:\n\
+        \x20 tui                         Run the terminal UI (default)\n\
+        \x20 daemon                      Evaluate the triggers without a terminal, reload on SIGHUP\n\
+        \x20 attach                      Run the terminal UI showing the bot of the running daemon\n\
+        \x20 watch SYMBOL...             Print the prices of the symbols as they are read\n\
+        \x20 price SYMBOL                Print the current price of the symbol\n\
+        \x20 symbols [TEXT]              List the symbols of the exchange containing the text\n\
+        \x20 triggers [list]             List the saved triggers\n\
+        \x20 triggers add [SYMBOL] ARGS  Add a trigger, e.g. 'triggers add BTCUSDT below 20000'\n\
+        \x20 triggers rm NUMBER          Remove the trigger\n\
+        \x20 export [PATH]               Write the saved triggers and watchlist as JSON\n\
+        \x20 import PATH                 Replace the saved triggers and watchlist\n\
+        \x20 config check                Validate and print the effective config\n\
+        \n\
+        Flags:\n\
+        \x20 --config PATH               Config file to load instead of the default one\n\
+        \x20 -h, --help                  Print this help\n",
+    );
+    for key in Config::keys() {
+        usage += &format!(
+            "  {} VALUE  ({})\n",
+            config::flag_name(&key),
+            config::env_name(&key)
+        );
+    }
+    usage += "\nThe flags override the environment variables which override the config file.\n\
+        The saved state shouldn't be edited while the TUI is running as it overwrites it.\n";
+    usage
+}
+
+#[test]
+fn parse_arguments() {
+    let args = |args: &[&str]| Cli::parse(args.iter().map(|arg| arg.to_string()));
+
+    let cli = args(&[
+        "config",
+        "check",
+        "--symbol",
+        "btcusdt",
+        "--sinks-bell=false",
+    ])
+    .unwrap();
+    assert!(matches!(cli.command, CliCommand::ConfigCheck));
+    assert_eq!(
+        cli.overrides,
+        [
+            ("symbol".to_owned(), "btcusdt".to_owned()),
+            ("sinks.bell".to_owned(), "false".to_owned())
+        ]
+    );
+
+    let cli = args(&["--config", "my.toml"]).unwrap();
+    assert!(matches!(cli.command, CliCommand::Tui));
+    assert_eq!(cli.config, Some(PathBuf::from("my.toml")));
+
+    let cli = args(&["triggers", "add", "btcusdt", "below", "20000", "bell"]).unwrap();
+    let CliCommand::Triggers(TriggersCommand::Add(trigger)) = cli.command else {
+        panic!("expected a new trigger, got {:?}", cli.command);
+    };
+    assert_eq!(trigger.symbol, Some(Symbol::new("BTCUSDT").unwrap()));
+    assert_eq!(trigger.to_string(), "BTCUSDT Price <= 20000 (bell)");
+    let cli = args(&["triggers", "add", "imbalance", "above", "30"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        CliCommand::Triggers(TriggersCommand::Add(PriceTrigger { symbol: None, .. }))
+    ));
+    let cli = args(&["triggers", "rm", "2"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        CliCommand::Triggers(TriggersCommand::Remove(1))
+    ));
+    let cli = args(&["symbols", "eth"]).unwrap();
+    assert!(matches!(&cli.command, CliCommand::Symbols(Some(query)) if query == "eth"));
+    let cli = args(&["watch", "ethusdt", "btcusdt"]).unwrap();
+    assert!(
+        matches!(&cli.command, CliCommand::Watch(symbols) if symbols == &["ETHUSDT", "BTCUSDT"])
+    );
+
+    assert!(args(&["--symbol"]).is_err());
+    assert!(args(&["--colour", "red"]).is_err());
+    assert!(args(&["launch"]).is_err());
+    assert!(args(&["watch"]).is_err());
+    assert!(args(&["triggers", "add", "btcusdt"]).is_err());
+    assert!(args(&["triggers", "rm", "0"]).is_err());
+}
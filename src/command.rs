@@ -0,0 +1,239 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    bot::{PriceLevel, PriceTrigger, TriggerCondition, TriggerTarget},
+    currency::Currency,
+    decimal::Decimal,
+    tui::{Panel, WatchlistSort},
+};
+
+/// Commands which can be entered trough the Input Box.
+///
+/// Syntax:
+/// - `add` - opens the trigger form
+/// - `add <above|below|>=|<=> <price> [in <currency>] [tui|bell]` - adds a new price trigger,
+///   comparing the price converted into the currency if it is given
+/// - `add imbalance <above|below|>=|<=> <percent>` - adds a new order book imbalance trigger
+/// - `add trade above <notional>` - adds a trigger on single trades larger than the notional value
+/// - `rm <trigger number>` - removes the price trigger
+/// - `enable <trigger number>` - enables the price trigger
+/// - `disable <trigger number>` - disables the price trigger
+/// - `watch` - opens the symbol picker
+/// - `watch <symbol>` / `unwatch <symbol>` - adds or removes the symbol from the watchlist
+/// - `currency <currency|off>` - shows the prices also in the currency
+/// - `sort <none|change|volume>` - sorts the watchlist
+/// - `show <panel>` / `hide <panel>` - shows or hides the TUI panel
+/// - `exit` - exits the application
+#[derive(Debug)]
+pub enum Command {
+    OpenTriggerForm,
+    AddTrigger(PriceTrigger),
+    RemoveTrigger(usize),
+    EnableTrigger(usize, bool),
+    OpenSymbolPicker,
+    Watch(String),
+    Unwatch(String),
+    SetCurrency(Option<Currency>),
+    SortWatchlist(WatchlistSort),
+    ShowPanel(Panel, bool),
+    Exit,
+}
+
+impl FromStr for Command {
+    type Err = ParseError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut words = line.split_whitespace();
+        let name = words.next().ok_or(ParseError::Empty)?;
+
+        let command = match name.to_lowercase().as_str() {
+            "add" => {
+                let mut words = words.by_ref().peekable();
+                if words.peek().is_none() {
+                    return Ok(Command::OpenTriggerForm);
+                }
+                // Triggers compare the price unless another target is given
+                let target = match words.peek().map(|word| word.to_lowercase()).as_deref() {
+                    Some("price") => {
+                        words.next();
+                        TriggerTarget::Price
+                    }
+                    Some("imbalance") => {
+                        words.next();
+                        TriggerTarget::Imbalance
+                    }
+                    Some("trade") => {
+                        words.next();
+                        TriggerTarget::Trade
+                    }
+                    _ => TriggerTarget::Price,
+                };
+                let condition = match words.next() {
+                    Some("above" | ">=") => TriggerCondition::HigherEq,
+                    // Every small trade would fire a trigger on trades below a value
+                    Some("below" | "<=") if target != TriggerTarget::Trade => {
+                        TriggerCondition::LowerEq
+                    }
+                    Some(other) => return Err(ParseError::InvalidArgument(other.to_owned())),
+                    None => return Err(ParseError::MissingArgument("condition")),
+                };
+                let level: Decimal = match target {
+                    TriggerTarget::Price => parse_next(&mut words, "price")?,
+                    TriggerTarget::Imbalance => parse_next(&mut words, "percent")?,
+                    TriggerTarget::Trade => parse_next(&mut words, "notional")?,
+                };
+                let mut trigger = PriceTrigger::with_target(PriceLevel(level), target, condition);
+                if words
+                    .next_if(|word| word.eq_ignore_ascii_case("in"))
+                    .is_some()
+                {
+                    trigger.currency = Some(parse_next(&mut words, "currency")?);
+                }
+                if trigger.validate().is_err() {
+                    return Err(ParseError::InvalidArgument(level.to_string()));
+                }
+                if let Some(delivery) = words.peek().and_then(|word| word.parse().ok()) {
+                    trigger.delivery = delivery;
+                    words.next();
+                }
+                Command::AddTrigger(trigger)
+            }
+            "rm" | "remove" => Command::RemoveTrigger(parse_trigger_index(&mut words)?),
+            "enable" => Command::EnableTrigger(parse_trigger_index(&mut words)?, true),
+            "disable" => Command::EnableTrigger(parse_trigger_index(&mut words)?, false),
+            "watch" => match words.next() {
+                Some(symbol) => Command::Watch(symbol.to_owned()),
+                None => Command::OpenSymbolPicker,
+            },
+            "unwatch" => Command::Unwatch(parse_next(&mut words, "symbol")?),
+            "currency" => match words.next() {
+                Some(off) if off.eq_ignore_ascii_case("off") => Command::SetCurrency(None),
+                Some(currency) => Command::SetCurrency(Some(
+                    currency
+                        .parse()
+                        .map_err(|_| ParseError::InvalidArgument(currency.to_owned()))?,
+                )),
+                None => return Err(ParseError::MissingArgument("currency")),
+            },
+            "sort" => Command::SortWatchlist(parse_next(&mut words, "sort order")?),
+            "show" => Command::ShowPanel(parse_next(&mut words, "panel")?, true),
+            "hide" => Command::ShowPanel(parse_next(&mut words, "panel")?, false),
+            "exit" | "quit" => Command::Exit,
+            _ => return Err(ParseError::UnknownCommand(name.to_owned())),
+        };
+
+        match words.next() {
+            Some(extra) => Err(ParseError::InvalidArgument(extra.to_owned())),
+            None => Ok(command),
+        }
+    }
+}
+
+/// Triggers are numbered from 1 in the Trigger List.
+fn parse_trigger_index<'a>(words: &mut impl Iterator<Item = &'a str>) -> Result<usize, ParseError> {
+    let number: usize = parse_next(words, "trigger number")?;
+    if number == 0 {
+        return Err(ParseError::InvalidArgument(number.to_string()));
+    }
+    Ok(number - 1)
+}
+
+fn parse_next<'a, T: FromStr>(
+    words: &mut impl Iterator<Item = &'a str>,
+    argument: &'static str,
+) -> Result<T, ParseError> {
+    let word = words.next().ok_or(ParseError::MissingArgument(argument))?;
+    word.parse()
+        .map_err(|_| ParseError::InvalidArgument(word.to_owned()))
+}
+
+#[derive(Debug)]
+pub enum ParseError {
+    Empty,
+    UnknownCommand(String),
+    MissingArgument(&'static str),
+    InvalidArgument(String),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "empty command"),
+            ParseError::UnknownCommand(name) => write!(f, "unknown command '{name}'"),
+            ParseError::MissingArgument(arg) => write!(f, "missing {arg}"),
+            ParseError::InvalidArgument(arg) => write!(f, "invalid argument '{arg}'"),
+        }
+    }
+}
+
+#[test]
+fn parse_commands() {
+    assert!(matches!(
+        "add above 1210.5".parse(),
+        Ok(Command::AddTrigger(PriceTrigger {
+            condition: TriggerCondition::HigherEq,
+            ..
+        }))
+    ));
+    assert!(matches!(
+        "add imbalance below -30".parse(),
+        Ok(Command::AddTrigger(PriceTrigger {
+            target: TriggerTarget::Imbalance,
+            condition: TriggerCondition::LowerEq,
+            ..
+        }))
+    ));
+    assert!(matches!(
+        "add below 1000 bell".parse(),
+        Ok(Command::AddTrigger(PriceTrigger {
+            delivery: crate::bot::Delivery::Bell,
+            ..
+        }))
+    ));
+    match "add below 1500 in eur bell".parse() {
+        Ok(Command::AddTrigger(trigger)) => {
+            assert_eq!(trigger.currency, Some(Currency::new("EUR").unwrap()));
+            assert_eq!(trigger.delivery, crate::bot::Delivery::Bell);
+        }
+        result => panic!("unexpected result {result:?}"),
+    }
+    assert!(matches!(
+        "add imbalance above 30 in eur".parse::<Command>(),
+        Err(ParseError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        "currency off".parse(),
+        Ok(Command::SetCurrency(None))
+    ));
+    assert!(matches!(
+        "currency btc".parse(),
+        Ok(Command::SetCurrency(Some(_)))
+    ));
+    assert!(matches!("add".parse(), Ok(Command::OpenTriggerForm)));
+    assert!(matches!(
+        "add trade below 1000".parse::<Command>(),
+        Err(ParseError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        "add imbalance above 150".parse::<Command>(),
+        Err(ParseError::InvalidArgument(_))
+    ));
+    assert!(matches!("watch".parse(), Ok(Command::OpenSymbolPicker)));
+    assert!(matches!("rm 3".parse(), Ok(Command::RemoveTrigger(2))));
+    assert!(matches!(
+        "disable 1".parse(),
+        Ok(Command::EnableTrigger(0, false))
+    ));
+    assert!(matches!(
+        "rm 0".parse::<Command>(),
+        Err(ParseError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        "add sideways 5".parse::<Command>(),
+        Err(ParseError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        "buy 5".parse::<Command>(),
+        Err(ParseError::UnknownCommand(_))
+    ));
+}
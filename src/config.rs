@@ -0,0 +1,416 @@
+use std::{
+    collections::HashMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{de::IntoDeserializer, Deserialize};
+
+use crate::{
+    bot::Bot,
+    currency::Currency,
+    daemon::DaemonConfig,
+    decimal::Decimal,
+    error::{Error, Result},
+    exchange,
+    http::HttpConfig,
+    keymap::{Action, KeyBinding},
+    layout::LayoutNode,
+    save::SaveData,
+    sinks::SinkConfig,
+    symbol::Symbol,
+    theme::Theme,
+};
+
+const CONFIG: &str = "config.toml";
+/// Directory of the config file inside the user config directory.
+const CONFIG_DIR: &str = "crypto-alertabot";
+/// Prefix of the environment variables overriding the config.
+const ENV_PREFIX: &str = "ALERTABOT_";
+
+/// User configuration loaded from the `config.toml` file.
+///
+/// Every field is optional and missing fields fall back to their defaults. The
+/// environment variables and the command line flags override the file, see
+/// [`load_config`].
+#[derive(Debug, Default, Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Main symbol whose price, order book and trades are tracked.
+    pub symbol: Option<Symbol>,
+
+    /// Symbols added to the watchlist on start.
+    pub watchlist: Vec<Symbol>,
+
+    /// Currency the prices are also shown in, like `USD`, `EUR` or `BTC`.
+    pub currency: Option<Currency>,
+
+    /// Milliseconds between the reads of the market data.
+    pub poll_interval_ms: Option<u64>,
+
+    /// Name of a built-in theme (`dark`, `light` or `high-contrast`) or of a
+    /// user theme found in the `themes` directory next to the executable.
+    pub theme: Option<String>,
+
+    /// Notional value in the quote asset from which trades are highlighted
+    /// in the Trades panel.
+    pub large_trade_notional: Option<Decimal>,
+
+    /// Seconds without new prices of the main symbol after which an alert is raised.
+    pub stale_alert_after: Option<u64>,
+
+    /// Where the alerts are delivered besides the TUI.
+    pub sinks: SinkConfig,
+
+    /// Files of the headless daemon.
+    pub daemon: DaemonConfig,
+
+    /// JSON API served by the daemon over HTTP.
+    pub http: HttpConfig,
+
+    /// Control mode key bindings which replace the default ones, for example:
+    ///
+    /// ```toml
+    /// [keymap]
+    /// quit = ["q", "Ctrl-c"]
+    /// ```
+    pub keymap: HashMap<Action, Vec<KeyBinding>>,
+
+    /// Arrangement of the TUI panels, see [`LayoutNode`].
+    pub layout: Option<LayoutNode>,
+}
+
+impl Config {
+    /// Keys which can be overridden besides the `keymap.<action>` keys.
+    const KEYS: &[&str] = &[
+        "symbol",
+        "watchlist",
+        "currency",
+        "poll_interval_ms",
+        "theme",
+        "large_trade_notional",
+        "stale_alert_after",
+        "sinks.bell",
+        "sinks.file",
+        "sinks.command",
+        "daemon.log_file",
+        "daemon.pid_file",
+        "daemon.socket",
+        "http.listen",
+    ];
+
+    /// Returns every key which can be overridden by the environment and the flags.
+    pub fn keys() -> impl Iterator<Item = String> {
+        Self::KEYS.iter().map(|&key| key.to_owned()).chain(
+            Action::ALL
+                .iter()
+                .map(|action| format!("keymap.{}", action.name())),
+        )
+    }
+
+    /// Sets the config value called `key` from its text form.
+    ///
+    /// Lists like the watchlist and the key bindings are separated by commas. The
+    /// symbols and the currency are normalized the same way as in the config file.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let invalid = || Error::config(format!("invalid value '{value}' for '{key}'"));
+        let list = || {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+        };
+
+        match key {
+            "symbol" => self.symbol = Some(Symbol::new(value)?),
+            "watchlist" => self.watchlist = list().map(Symbol::new).collect::<Result<_>>()?,
+            "currency" => self.currency = Some(Currency::new(value)?),
+            "poll_interval_ms" => {
+                let ms = value.parse().ok().filter(|&ms| ms > 0);
+                self.poll_interval_ms = Some(ms.ok_or_else(invalid)?)
+            }
+            "theme" => self.theme = Some(value.to_owned()),
+            "large_trade_notional" => {
+                let notional = value
+                    .parse()
+                    .ok()
+                    .filter(|&notional| notional > Decimal::ZERO);
+                self.large_trade_notional = Some(notional.ok_or_else(invalid)?)
+            }
+            "stale_alert_after" => {
+                let seconds = value.parse().ok().filter(|&seconds| seconds > 0);
+                self.stale_alert_after = Some(seconds.ok_or_else(invalid)?)
+            }
+            "sinks.bell" => self.sinks.bell = value.parse().map_err(|_| invalid())?,
+            "sinks.file" => self.sinks.file = Some(PathBuf::from(value)),
+            "sinks.command" => self.sinks.command = Some(value.to_owned()),
+            "daemon.log_file" => self.daemon.log_file = Some(PathBuf::from(value)),
+            "daemon.pid_file" => self.daemon.pid_file = Some(PathBuf::from(value)),
+            "daemon.socket" => self.daemon.socket = Some(PathBuf::from(value)),
+            "http.listen" => self.http.listen = Some(value.to_owned()),
+            _ => {
+                let action = key
+                    .strip_prefix("keymap.")
+                    .and_then(|name| Action::ALL.iter().find(|a| a.name() == name))
+                    .ok_or_else(|| Error::config(format!("unknown config key '{key}'")))?;
+                let bindings = list()
+                    .map(|binding| {
+                        KeyBinding::deserialize(binding.into_deserializer())
+                            .map_err(|err: serde::de::value::Error| Error::config(err.to_string()))
+                    })
+                    .collect::<Result<Vec<KeyBinding>>>()?;
+                self.keymap.insert(*action, bindings);
+            }
+        }
+        Ok(())
+    }
+
+    /// Overrides the values whose environment variables are set, e.g.
+    /// `ALERTABOT_POLL_INTERVAL_MS` for `poll_interval_ms`.
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<()> {
+        for key in Self::keys() {
+            let name = env_name(&key);
+            if let Some(value) = var(&name) {
+                self.set(&key, &value)
+                    .map_err(|err| err.with_context(name))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn symbol(&self) -> &str {
+        self.symbol
+            .as_ref()
+            .map_or(Bot::DEFAULT_SYMBOL, Symbol::as_str)
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval_ms
+            .map(Duration::from_millis)
+            .unwrap_or(crate::TICK_INTERVAL)
+    }
+
+    /// Creates the bot tracking the symbols of the config and restores the `save_data`.
+    pub fn create_bot(&self, save_data: Option<SaveData>) -> Result<Bot> {
+        let mut bot = Bot::with_symbol(Symbol::new(self.symbol())?, self.poll_interval())?;
+        for symbol in &self.watchlist {
+            bot.watch(symbol.as_str())?;
+        }
+        if let Some(notional) = self.large_trade_notional {
+            bot.set_large_trade_notional(notional);
+        }
+        bot.set_stale_alert_after(self.stale_alert_after.map(Duration::from_secs));
+        if let Some(currency) = &self.currency {
+            bot.set_currency(Some(currency.clone()))?;
+        }
+        if let Some(data) = save_data {
+            bot.load(data);
+        }
+        Ok(bot)
+    }
+
+    /// Checks the values which can't be checked while parsing, including that
+    /// the theme exists and that the symbols are listed on the exchange.
+    pub fn validate(&self) -> Result<()> {
+        self.check_values()?;
+        let watchlist = self.watchlist.iter().map(Symbol::as_str);
+        exchange::check_cached(std::iter::once(self.symbol()).chain(watchlist))?;
+        if let Some(name) = &self.theme {
+            Theme::load(name)?;
+        }
+        Ok(())
+    }
+
+    /// Checks the values which don't need the files of the exchange info or the themes.
+    fn check_values(&self) -> Result<()> {
+        let must_be_positive = |key: &str| Error::config(format!("'{key}' must be positive"));
+        if self.poll_interval_ms == Some(0) {
+            return Err(must_be_positive("poll_interval_ms"));
+        }
+        if self
+            .large_trade_notional
+            .is_some_and(|notional| notional <= Decimal::ZERO)
+        {
+            return Err(must_be_positive("large_trade_notional"));
+        }
+        if self.stale_alert_after == Some(0) {
+            return Err(must_be_positive("stale_alert_after"));
+        }
+        if let Some(layout) = &self.layout {
+            layout.validate().map_err(Error::config)?;
+        }
+        Ok(())
+    }
+
+    /// Formats the config the same way as the config file.
+    pub fn to_toml(&self) -> Result<String> {
+        // Converted to a value first so the tables are written after the plain values
+        Ok(toml::to_string_pretty(&toml::Value::try_from(self)?)?)
+    }
+}
+
+/// Name of the environment variable overriding the config `key`.
+pub fn env_name(key: &str) -> String {
+    format!("{ENV_PREFIX}{}", key.replace('.', "_").to_uppercase())
+}
+
+/// Name of the command line flag overriding the config `key`.
+pub fn flag_name(key: &str) -> String {
+    format!("--{}", key.replace(['.', '_'], "-"))
+}
+
+/// Loads the config merging the layers in this order:
+///
+/// 1. the config file given by `path` or `ALERTABOT_CONFIG`, or else the first one
+///    found in the user config directory (`$XDG_CONFIG_HOME/crypto-alertabot`) or
+///    next to the executable,
+/// 2. the environment variables, see [`env_name`],
+/// 3. the `flags` given as `(key, value)`.
+///
+/// Returns the config with the path of the loaded config file.
+pub fn load_config(
+    path: Option<&Path>,
+    flags: &[(String, String)],
+) -> Result<(Config, Option<PathBuf>)> {
+    let explicit = path
+        .map(Path::to_path_buf)
+        .or_else(|| env::var_os(env_name("config")).map(PathBuf::from));
+    let (mut config, path) = match explicit {
+        Some(path) => (
+            read_config(&path)?.ok_or_else(|| not_found(&path))?,
+            Some(path),
+        ),
+        None => find_config()?,
+    };
+
+    config.apply_env(|name| env::var(name).ok())?;
+    for (key, value) in flags {
+        config
+            .set(key, value)
+            .map_err(|err| err.with_context(flag_name(key)))?;
+    }
+    Ok((config, path))
+}
+
+fn not_found(path: &Path) -> Error {
+    Error::config(format!("config file {} not found", path.display()))
+}
+
+/// Loads the first config file which is found.
+///
+/// If **no** config file is found the default config is returned.
+fn find_config() -> Result<(Config, Option<PathBuf>)> {
+    let mut candidates = Vec::new();
+    if let Some(dir) = user_config_dir() {
+        candidates.push(dir.join(CONFIG_DIR).join(CONFIG));
+    }
+    candidates.push(std::env::current_exe()?.with_file_name(CONFIG));
+
+    for path in candidates {
+        if let Some(config) = read_config(&path)? {
+            return Ok((config, Some(path)));
+        }
+    }
+    Ok((Config::default(), None))
+}
+
+/// Reads the config file at `path` returning `None` if it doesn't exist.
+fn read_config(path: &Path) -> Result<Option<Config>> {
+    match fs::read_to_string(path) {
+        Ok(data) => {
+            let config: Config = toml::from_str(&data)?;
+            if let Some(layout) = &config.layout {
+                layout.validate().map_err(Error::config)?;
+            }
+            Ok(Some(config))
+        }
+        Err(err) => match err.kind() {
+            io::ErrorKind::NotFound => Ok(None),
+            _ => Err(err.into()),
+        },
+    }
+}
+
+#[cfg(not(windows))]
+fn user_config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+}
+
+#[cfg(windows)]
+fn user_config_dir() -> Option<PathBuf> {
+    env::var_os("APPDATA").map(PathBuf::from)
+}
+
+/// Validates the config and prints it with all the overrides applied.
+pub fn check(config: &Config, path: Option<&Path>) -> Result<()> {
+    config.validate()?;
+    match path {
+        Some(path) => println!("# config file: {}", path.display()),
+        None => println!("# no config file found, using the defaults"),
+    }
+    print!("{}", config.to_toml()?);
+    Ok(())
+}
+
+#[test]
+fn config_layers() {
+    let mut config: Config = toml::from_str(
+        r#"
+        symbol = "BTCUSDT"
+        poll_interval_ms = 500
+
+        [sinks]
+        bell = false
+        "#,
+    )
+    .unwrap();
+
+    config
+        .apply_env(|name| match name {
+            "ALERTABOT_POLL_INTERVAL_MS" => Some("2000".to_owned()),
+            "ALERTABOT_WATCHLIST" => Some("ethbtc, bnbusdt".to_owned()),
+            _ => None,
+        })
+        .unwrap();
+    config.set("symbol", " ethbtc").unwrap();
+    config.set("currency", "eur").unwrap();
+    config.set("keymap.quit", "q,Ctrl-c").unwrap();
+
+    assert_eq!(config.symbol(), "ETHBTC");
+    assert_eq!(config.poll_interval(), Duration::from_millis(2000));
+    let watchlist = config.watchlist.iter().map(Symbol::as_str);
+    assert_eq!(watchlist.collect::<Vec<&str>>(), ["ETHBTC", "BNBUSDT"]);
+    assert_eq!(config.currency.as_ref().map(Currency::as_str), Some("EUR"));
+    assert!(!config.sinks.bell);
+    assert_eq!(config.keymap[&Action::Quit].len(), 2);
+    // Without the exchange info and the themes which are read from the disk
+    assert!(config.check_values().is_ok());
+
+    assert!(config.set("poll_interval_ms", "soon").is_err());
+    assert!(config.set("poll_interval_ms", "0").is_err());
+    for notional in ["0", "-5", "NaN", "inf"] {
+        assert!(config.set("large_trade_notional", notional).is_err());
+    }
+    assert!(config.set("stale_alert_after", "-1").is_err());
+    config.large_trade_notional = Some(Decimal::ZERO);
+    assert!(config.check_values().is_err());
+    config.large_trade_notional = None;
+    assert!(config.set("watchlist", "btcusdt, eth/usdt").is_err());
+    assert!(config.set("keymap.jump", "j").is_err());
+    assert_eq!(flag_name("sinks.bell"), "--sinks-bell");
+    assert_eq!(
+        env_name("keymap.new_trigger"),
+        "ALERTABOT_KEYMAP_NEW_TRIGGER"
+    );
+
+    let printed: Config = toml::from_str(&config.to_toml().unwrap()).unwrap();
+    assert_eq!(printed.symbol(), "ETHBTC");
+    // The config file is normalized like the overrides
+    let file: Config = toml::from_str(r#"symbol = "ethusdt""#).unwrap();
+    assert_eq!(file.symbol(), "ETHUSDT");
+    assert!(toml::from_str::<Config>(r#"watchlist = ["eth usdt"]"#).is_err());
+}
@@ -0,0 +1,267 @@
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use binance::{
+    api::Binance,
+    errors::{Error as BinanceError, Result as BinanceResult},
+    market::Market,
+};
+
+/// How well the market data is being read, ordered from the best to the worst.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Default,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionState {
+    #[default]
+    Connected,
+    /// Some reads failed or the data is getting old.
+    Degraded,
+    /// Several reads in a row failed.
+    Down,
+}
+
+impl ConnectionState {
+    /// Number of failed reads in a row after which the connection is down.
+    pub const DOWN_AFTER_FAILURES: u32 = 3;
+    /// Data which wasn't read for this long is stale and makes the connection degraded.
+    pub const STALE_AFTER: Duration = Duration::from_secs(10);
+
+    /// Rates a reader from its failed reads in a row and when it last read the data.
+    ///
+    /// Readers which can legitimately go quiet, like the trade stream, pass `None`
+    /// as `updated`.
+    pub fn rate(failures: u32, updated: Option<Instant>) -> Self {
+        let stale = updated.is_some_and(|updated| updated.elapsed() > Self::STALE_AFTER);
+        if failures >= Self::DOWN_AFTER_FAILURES {
+            ConnectionState::Down
+        } else if failures > 0 || stale {
+            ConnectionState::Degraded
+        } else {
+            ConnectionState::Connected
+        }
+    }
+}
+
+impl Display for ConnectionState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectionState::Connected => write!(f, "connected"),
+            ConnectionState::Degraded => write!(f, "degraded"),
+            ConnectionState::Down => write!(f, "down"),
+        }
+    }
+}
+
+/// Exponential backoff with jitter between the retries of failed reads so an
+/// unreachable exchange isn't asked again every second.
+#[derive(Debug)]
+pub struct Backoff {
+    /// Delay after the first failure.
+    base: Duration,
+    failures: u32,
+}
+
+impl Backoff {
+    const MAX_DELAY: Duration = Duration::from_secs(60);
+
+    pub fn new(base: Duration) -> Self {
+        Self { base, failures: 0 }
+    }
+
+    pub fn reset(&mut self) {
+        self.failures = 0;
+    }
+
+    /// Returns the delay before the next retry which doubles with every failure.
+    ///
+    /// The delay is randomly shortened by up to a half so the readers of
+    /// different symbols don't retry all at once.
+    pub fn next_delay(&mut self) -> Duration {
+        let exponential = self
+            .base
+            .saturating_mul(1 << self.failures.min(16))
+            .min(Self::MAX_DELAY);
+        self.failures += 1;
+        exponential.mul_f64(0.5 + 0.5 * random_fraction())
+    }
+}
+
+/// Returns a number between 0 and 1 which is random enough for the jitter.
+fn random_fraction() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    nanos as f64 / 1_000_000_000.0
+}
+
+/// Everything the reader threads need, shared by the trackers so they can
+/// respawn their threads.
+#[derive(Clone)]
+pub struct Readers {
+    pub market: Arc<Market>,
+    /// Time between the successful reads.
+    pub interval: Duration,
+    /// Unset to stop the reader threads.
+    pub running: Arc<AtomicBool>,
+}
+
+impl Readers {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            market: Arc::new(Market::new(None, None)),
+            interval,
+            running: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Makes every reader thread exit after its current read.
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+
+    /// Calls `read` in a new thread and sends every result to the returned receiver.
+    ///
+    /// Successful reads are repeated every [`Readers::interval`] while failed reads
+    /// are retried after a [`Backoff`]. The thread exits once the receiver is dropped
+    /// or the readers are stopped.
+    pub fn spawn<T, F>(&self, mut read: F) -> Receiver<BinanceResult<T>>
+    where
+        T: Send + 'static,
+        F: FnMut(&Market) -> BinanceResult<T> + Send + 'static,
+    {
+        let (tx, rx) = channel();
+        let readers = self.clone();
+        thread::spawn(move || {
+            let mut backoff = Backoff::new(readers.interval);
+            while readers.running.load(Ordering::Relaxed) {
+                let result = read(&readers.market);
+                let delay = match result {
+                    Ok(_) => {
+                        backoff.reset();
+                        readers.interval
+                    }
+                    Err(_) => backoff.next_delay(),
+                };
+                if tx.send(result).is_err() {
+                    break;
+                }
+                sleep_while_running(delay, &readers.running);
+            }
+        });
+        rx
+    }
+}
+
+/// Sleeps for `duration` but wakes up early once `running` is unset so the
+/// reader threads stop quickly even during a long backoff.
+pub fn sleep_while_running(duration: Duration, running: &AtomicBool) {
+    const STEP: Duration = Duration::from_millis(100);
+
+    let start = Instant::now();
+    while running.load(Ordering::Relaxed) {
+        let left = duration.saturating_sub(start.elapsed());
+        if left.is_zero() {
+            break;
+        }
+        thread::sleep(left.min(STEP));
+    }
+}
+
+/// Returns whether the thread sending to the `reader` has exited.
+pub fn is_stopped<T>(reader: &Receiver<T>) -> bool {
+    matches!(reader.try_recv(), Err(TryRecvError::Disconnected))
+}
+
+/// Takes every result waiting in the `reader`.
+///
+/// A reader thread which died, for example by panicking, is replaced by a new one
+/// from `respawn` and reported as a failed read.
+pub fn receive_all<T>(
+    reader: &mut Receiver<BinanceResult<T>>,
+    respawn: impl FnOnce() -> Receiver<BinanceResult<T>>,
+) -> Vec<BinanceResult<T>> {
+    let mut results = Vec::new();
+    loop {
+        match reader.try_recv() {
+            Ok(result) => results.push(result),
+            Err(TryRecvError::Empty) => break,
+            Err(TryRecvError::Disconnected) => {
+                *reader = respawn();
+                results.push(Err(BinanceError::from(
+                    "the reader thread stopped and was restarted",
+                )));
+                break;
+            }
+        }
+    }
+    results
+}
+
+#[test]
+fn backoff_grows_until_max_delay() {
+    let mut backoff = Backoff::new(crate::TICK_INTERVAL);
+    let delays = (0..10)
+        .map(|_| backoff.next_delay())
+        .collect::<Vec<Duration>>();
+    assert!(delays[0] >= crate::TICK_INTERVAL / 2 && delays[0] <= crate::TICK_INTERVAL);
+    assert!(delays[3] >= crate::TICK_INTERVAL * 4);
+    assert!(delays.iter().all(|&delay| delay <= Backoff::MAX_DELAY));
+
+    backoff.reset();
+    assert!(backoff.next_delay() <= crate::TICK_INTERVAL);
+}
+
+#[test]
+fn dead_reader_is_respawned() {
+    let (tx, mut reader) = channel::<BinanceResult<u8>>();
+    tx.send(Ok(1)).unwrap();
+    drop(tx);
+
+    let (respawned_tx, respawned) = channel();
+    respawned_tx.send(Ok(2)).unwrap();
+    let results = receive_all(&mut reader, || respawned);
+    assert!(matches!(results[..], [Ok(1), Err(_)]));
+    assert!(matches!(
+        receive_all(&mut reader, || unreachable!())[..],
+        [Ok(2)]
+    ));
+    assert_eq!(
+        ConnectionState::rate(ConnectionState::DOWN_AFTER_FAILURES, None),
+        ConnectionState::Down
+    );
+}
+
+#[test]
+#[allow(clippy::result_large_err)]
+fn stopped_reader_exits() {
+    let readers = Readers::new(crate::TICK_INTERVAL);
+    let reader = readers.spawn(|_| Err::<(), _>(BinanceError::from("offline")));
+    assert!(matches!(reader.recv(), Ok(Err(_))));
+
+    // The reader wakes up from its backoff instead of sleeping it through
+    readers.stop();
+    let start = Instant::now();
+    while !is_stopped(&reader) {
+        assert!(start.elapsed() < Duration::from_secs(1));
+        thread::sleep(Duration::from_millis(10));
+    }
+}
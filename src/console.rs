@@ -1,11 +1,20 @@
-use crossterm::event::KeyEvent;
+use std::collections::VecDeque;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
 use tui::{backend::Backend, Terminal};
 
-use crate::bot::Bot;
-use crate::error::Result;
+use crate::bot::{BotControl, BotSnapshot};
+use crate::command::Command;
+use crate::connection::ConnectionState;
+use crate::error::{Error, Result};
 use crate::{
     input::{InputHandler, Interruption},
-    tui::TUI,
+    keymap::{Action, Keymap},
+    layout::LayoutNode,
+    popup::{PendingAction, Popup, PopupResult, SymbolPicker, TriggerForm},
+    sinks::AlertSinks,
+    theme::Theme,
+    tui::{Panel, ScrollDirection, TUI},
 };
 
 pub struct Console<B: Backend> {
@@ -13,6 +22,16 @@ pub struct Console<B: Backend> {
     tui: TUI,
     input: InputHandler,
     input_mode: InputMode,
+    keymap: Keymap,
+
+    sinks: AlertSinks,
+    /// Number of the alerts which were already delivered.
+    delivered_alerts: usize,
+    /// Errors already shown in a popup so the same error isn't shown repeatedly,
+    /// forgotten once the connection recovers.
+    shown_errors: VecDeque<String>,
+    /// Connection state which was last written to the log.
+    connection: ConnectionState,
 
     should_exit: bool,
     //tick: u16,
@@ -20,15 +39,30 @@ pub struct Console<B: Backend> {
 
 impl<B: Backend> Console<B> {
     //const TICKS_PER_UPDATE: u16 = 1;
+    /// Number of the last errors remembered as shown.
+    const MAX_SHOWN_ERRORS: usize = 20;
 
-    pub fn new(terminal: Terminal<B>) -> Result<Self> {
-        let mut tui = TUI::new();
+    pub fn new(
+        terminal: Terminal<B>,
+        keymap: Keymap,
+        theme: Theme,
+        layout: LayoutNode,
+        sinks: AlertSinks,
+    ) -> Result<Self> {
+        let mut tui = TUI::new(theme, layout);
         tui.resize(terminal.size()?);
+        tui.set_editing(true);
         Ok(Self {
             terminal,
             tui,
             input: InputHandler::new(),
             input_mode: InputMode::Editing,
+            keymap,
+
+            sinks,
+            delivered_alerts: 0,
+            shown_errors: VecDeque::new(),
+            connection: ConnectionState::default(),
 
             should_exit: false,
             //tick: 0,
@@ -36,36 +70,330 @@ impl<B: Backend> Console<B> {
     }
 
     /// Increments the inner ticker and schedules TUI updates per `TICKS_PER_UPDATE`.
-    pub fn update(&mut self, bot: &Bot) {
+    pub fn update(&mut self, bot: &mut dyn BotControl) {
+        let snapshot = bot.snapshot();
+        self.deliver_alerts(&snapshot);
+        self.show_errors(bot.take_errors());
+        self.log_connection(snapshot.connection);
+
         //self.tick += 1;
         //
         //if self.tick >= Self::TICKS_PER_UPDATE {
         //    self.update_tui(bot)
         //    self.tick = 0;
         //};
-        self.update_tui(bot);
+        self.update_tui(&snapshot);
     }
 
-    fn update_tui(&mut self, bot: &Bot) {
+    fn update_tui(&mut self, bot: &BotSnapshot) {
         self.tui.update(bot)
     }
 
-    pub fn process_input(&mut self, event: KeyEvent) {
+    /// Treats the alerts raised so far as delivered, e.g. the alerts the daemon raised
+    /// before the TUI attached to it.
+    pub fn skip_alerts(&mut self, bot: &BotSnapshot) {
+        self.delivered_alerts = bot.alerts.raised();
+    }
+
+    /// Sends the new alerts to the alert sinks.
+    ///
+    /// It is also called while shutting down so no alert is left undelivered.
+    pub fn deliver_alerts(&mut self, bot: &BotSnapshot) {
+        // The alerts start over when the bot of the daemon is reloaded
+        if bot.alerts.raised() < self.delivered_alerts {
+            self.delivered_alerts = 0;
+        }
+        for alert in bot.alerts.since(self.delivered_alerts) {
+            self.sinks.deliver(alert);
+        }
+        self.delivered_alerts = bot.alerts.raised();
+        // Failing sinks are only logged since the alerts are still shown in the TUI
+        if let Err(err) = self.sinks.flush() {
+            self.tui.log_error(&err.with_context("alert sinks"));
+        }
+    }
+
+    /// Writes the errors to the log and shows the first new error in a popup
+    /// unless another popup is open.
+    ///
+    /// Errors which were already shown once only go to the log until the connection
+    /// recovers.
+    fn show_errors(&mut self, errors: Vec<Error>) {
+        for error in errors {
+            self.tui.log_error(&error);
+            let message = error.to_string();
+            if self.tui.popup_mut().is_none() && !self.shown_errors.contains(&message) {
+                self.tui
+                    .show_popup(Popup::error(error.title(), message.clone()));
+                if self.shown_errors.len() == Self::MAX_SHOWN_ERRORS {
+                    self.shown_errors.pop_front();
+                }
+                self.shown_errors.push_back(message);
+            }
+        }
+    }
+
+    /// Writes the connection state to the log when it changes, forgetting the shown
+    /// errors once it recovers so they are shown again if they come back.
+    fn log_connection(&mut self, connection: ConnectionState) {
+        if connection != self.connection {
+            self.tui
+                .log_info(format!("Connection to Binance is {connection}"));
+            if connection == ConnectionState::Connected {
+                self.shown_errors.clear();
+            }
+            self.connection = connection;
+        }
+    }
+
+    pub fn process_input(&mut self, event: KeyEvent, bot: &mut dyn BotControl) {
+        // The raw mode turns Ctrl-C into a key so it is handled like SIGINT in every mode
+        if event.code == KeyCode::Char('c') && event.modifiers.contains(KeyModifiers::CONTROL) {
+            self.request_exit("interrupted");
+            return;
+        }
+
+        if let Some(popup) = self.tui.popup_mut() {
+            let result = popup.process_key(event);
+            self.process_popup_result(result, bot);
+            return;
+        }
+
         match self.input_mode {
-            InputMode::Editing => self.process_editing(event),
-            InputMode::Control => self.process_controls(event),
+            InputMode::Editing => self.process_editing(event, bot),
+            InputMode::Control => self.process_controls(event, bot),
+        }
+    }
+
+    /// Single line pastes are inserted into the input buffer while multi-line
+    /// pastes are executed as a batch of commands, one command per line, stopping
+    /// after a command exits.
+    ///
+    /// Pastes are only accepted in the Editing mode while no popup is open.
+    pub fn process_paste(&mut self, text: String, bot: &mut dyn BotControl) {
+        if !matches!(self.input_mode, InputMode::Editing) || self.tui.popup_mut().is_some() {
+            return;
+        }
+        if !text.contains(['\n', '\r']) {
+            self.input.insert_str(&text);
+            self.tui.update_input(&self.input);
+            return;
+        }
+
+        let mut reports = Vec::new();
+        for (i, line) in pasted_lines(&text).into_iter().enumerate() {
+            reports.push(format!("{}: {}", i + 1, self.execute(line, bot)));
+            if self.should_exit {
+                break;
+            }
+        }
+        self.tui.report(reports);
+    }
+
+    /// Clicking the Input Box enters the Editing mode while clicking
+    /// any other panel switches to the Control mode.
+    pub fn process_mouse(&mut self, event: MouseEvent) {
+        if self.tui.popup_mut().is_some() {
+            return;
+        }
+        match self.tui.process_mouse(event) {
+            Some(Panel::InputBox) => self.set_input_mode(InputMode::Editing),
+            Some(_) => self.set_input_mode(InputMode::Control),
+            None => (),
+        }
+    }
+
+    fn process_popup_result(&mut self, result: Option<PopupResult>, bot: &mut dyn BotControl) {
+        let command = match result {
+            None => return,
+            Some(PopupResult::Close) => None,
+            Some(PopupResult::Confirmed(PendingAction::RemoveTrigger(index))) => {
+                Some(Command::RemoveTrigger(index))
+            }
+            Some(PopupResult::Submitted(trigger)) => Some(Command::AddTrigger(trigger)),
+            Some(PopupResult::Picked(symbol)) => Some(Command::Watch(symbol)),
+        };
+        let report = command.map(|command| match self.run(command, bot) {
+            Ok(report) => report,
+            Err(err) => {
+                let report = format!("error: {err}");
+                self.tui.log_error(&err);
+                report
+            }
+        });
+        self.tui.close_popup();
+        self.tui.update_triggers(&bot.snapshot().triggers);
+        if let Some(report) = report {
+            self.tui.report(vec![report]);
+        }
+    }
+
+    /// Asks for a confirmation before removing the trigger selected in the Trigger List.
+    fn confirm_remove_trigger(&mut self, bot: &dyn BotControl) {
+        let Some(index) = self.tui.selected_trigger() else {
+            self.tui
+                .report(vec!["error: no trigger is selected".to_owned()]);
+            return;
+        };
+        if let Some(trigger) = bot.snapshot().triggers.get(index) {
+            self.tui.show_popup(Popup::Confirm {
+                message: format!("Delete trigger {} ({trigger})?", index + 1),
+                action: PendingAction::RemoveTrigger(index),
+            });
         }
     }
 
-    fn process_controls(&mut self, event: KeyEvent) {}
+    fn process_controls(&mut self, event: KeyEvent, bot: &mut dyn BotControl) {
+        let action = self.keymap.action(event);
 
-    fn process_editing(&mut self, event: KeyEvent) {
+        // Any key closes the help overlay
+        if self.tui.is_help_shown() {
+            self.tui.hide_help();
+            if action != Some(Action::Quit) {
+                return;
+            }
+        }
+
+        match action {
+            Some(Action::FocusNext) => self.tui.focus_next(),
+            Some(Action::FocusPrevious) => self.tui.focus_previous(),
+            Some(Action::ScrollUp) => self.tui.scroll(ScrollDirection::Up),
+            Some(Action::ScrollDown) => self.tui.scroll(ScrollDirection::Down),
+            Some(Action::ToggleMaximized) => self.tui.toggle_maximized(),
+            Some(Action::CycleWatchlistSort) => self.tui.cycle_watchlist_sort(),
+            Some(Action::NewTrigger) => self
+                .tui
+                .show_popup(Popup::TriggerForm(TriggerForm::new(bot.snapshot().symbol))),
+            Some(Action::RemoveTrigger) => self.confirm_remove_trigger(bot),
+            Some(Action::PickSymbol) => {
+                if let Err(err) = self.run(Command::OpenSymbolPicker, bot) {
+                    self.tui.report(vec![format!("error: {err}")]);
+                }
+            }
+            Some(Action::EnterEditing) => self.set_input_mode(InputMode::Editing),
+            Some(Action::Help) => self.tui.show_help(self.keymap.help()),
+            Some(Action::Quit) => self.should_exit = true,
+            None => (),
+        }
+    }
+
+    fn process_editing(&mut self, event: KeyEvent, bot: &mut dyn BotControl) {
         if let Some(interruption) = self.input.process_input(event) {
             match interruption {
-                Interruption::Enter(buf) => println!("You entered: {buf}"),
-                Interruption::Esc => self.should_exit = true,
+                // Empty lines are skipped like in the pasted commands
+                Interruption::Enter(buf) if buf.trim().is_empty() => (),
+                Interruption::Enter(buf) => {
+                    let report = self.execute(buf.trim(), bot);
+                    self.tui.report(vec![report]);
+                }
+                Interruption::Esc => self.set_input_mode(InputMode::Control),
             }
         }
+        self.tui.update_input(&self.input);
+    }
+
+    fn set_input_mode(&mut self, mode: InputMode) {
+        self.input_mode = mode;
+        self.tui.set_editing(matches!(mode, InputMode::Editing));
+    }
+
+    /// Parses and executes the command `line` returning a short report of the result.
+    ///
+    /// Failed commands are also written to the log.
+    fn execute(&mut self, line: &str, bot: &mut dyn BotControl) -> String {
+        let result = line
+            .parse::<Command>()
+            .map_err(Error::from)
+            .and_then(|command| self.run(command, bot));
+        self.tui.update_triggers(&bot.snapshot().triggers);
+        match result {
+            Ok(report) => format!("{line} -> {report}"),
+            Err(err) => {
+                let report = format!("{line} -> error: {err}");
+                self.tui.log_error(&err.with_context(line));
+                report
+            }
+        }
+    }
+
+    fn run(&mut self, command: Command, bot: &mut dyn BotControl) -> Result<String> {
+        let no_trigger = |index: usize| Error::command(format!("no trigger {}", index + 1));
+        let report = match command {
+            Command::OpenTriggerForm => {
+                let form = TriggerForm::new(bot.snapshot().symbol);
+                self.tui.show_popup(Popup::TriggerForm(form));
+                "opened the trigger form".to_owned()
+            }
+            Command::AddTrigger(trigger) => {
+                let message = format!("added trigger {trigger}");
+                bot.add_trigger(trigger)?;
+                message
+            }
+            Command::RemoveTrigger(index) => {
+                let trigger = bot
+                    .remove_trigger(index)?
+                    .ok_or_else(|| no_trigger(index))?;
+                format!("removed trigger {trigger}")
+            }
+            Command::EnableTrigger(index, enabled) => {
+                let trigger = bot
+                    .set_trigger_enabled(index, enabled)?
+                    .ok_or_else(|| no_trigger(index))?;
+                if enabled {
+                    format!("enabled trigger {trigger}")
+                } else {
+                    format!("disabled trigger {trigger}")
+                }
+            }
+            Command::OpenSymbolPicker => {
+                let exchange = bot.exchange_info().ok_or_else(|| {
+                    Error::command("the symbols of the exchange aren't loaded yet")
+                })?;
+                self.tui
+                    .show_popup(Popup::SymbolPicker(SymbolPicker::new(exchange)));
+                "opened the symbol picker".to_owned()
+            }
+            Command::Watch(symbol) => {
+                let symbol = symbol.to_uppercase();
+                if !bot.watch(&symbol)? {
+                    return Err(Error::command(format!("{symbol} is already watched")));
+                }
+                format!("watching {symbol}")
+            }
+            Command::Unwatch(symbol) => {
+                let symbol = symbol.to_uppercase();
+                if !bot.unwatch(&symbol)? {
+                    return Err(Error::command(format!("{symbol} isn't in the watchlist")));
+                }
+                format!("stopped watching {symbol}")
+            }
+            Command::SetCurrency(currency) => {
+                bot.set_currency(currency.clone())?;
+                match currency {
+                    Some(currency) => format!("showing the prices also in {currency}"),
+                    None => "showing the prices only in their quote assets".to_owned(),
+                }
+            }
+            Command::SortWatchlist(sort) => {
+                self.tui.set_watchlist_sort(sort);
+                format!("sorting the watchlist by {sort}")
+            }
+            Command::ShowPanel(panel, shown) => {
+                self.tui
+                    .set_panel_shown(panel, shown)
+                    .map_err(Error::command)?;
+                if shown {
+                    format!("showing the '{panel}' panel")
+                } else {
+                    format!("hid the '{panel}' panel")
+                }
+            }
+            Command::Exit => {
+                self.should_exit = true;
+                "exiting".to_owned()
+            }
+        };
+        Ok(report)
     }
 
     pub fn render(&mut self) -> Result<()> {
@@ -81,6 +409,12 @@ impl<B: Backend> Console<B> {
     pub fn should_exit(&self) -> bool {
         self.should_exit
     }
+
+    /// Makes the main loop exit, showing the `reason` in the Input Box.
+    pub fn request_exit(&mut self, reason: &str) {
+        self.tui.report(vec![format!("{reason} -> shutting down")]);
+        self.should_exit = true;
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -88,3 +422,25 @@ pub enum InputMode {
     Editing,
     Control,
 }
+
+/// Splits a paste into its non-empty lines.
+///
+/// Terminals usually send the pasted newlines as `\r` so both are line breaks.
+fn pasted_lines(text: &str) -> Vec<&str> {
+    text.split(['\r', '\n'])
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+#[test]
+fn paste_lines() {
+    assert_eq!(
+        pasted_lines("add above 1\radd below 2"),
+        ["add above 1", "add below 2"]
+    );
+    assert_eq!(
+        pasted_lines("watch btcusdt\r\n\r\n  sort change \n"),
+        ["watch btcusdt", "sort change"]
+    );
+}
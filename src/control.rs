@@ -0,0 +1,621 @@
+use std::{
+    collections::HashMap,
+    fs::{self, DirBuilder},
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::{DirBuilderExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    bot::{Alert, Bot, BotControl, BotSnapshot, PriceTrigger},
+    currency::Currency,
+    decimal::Decimal,
+    error::{Error, Result},
+    exchange::{self, ExchangeInfo},
+    save::SaveData,
+    symbol::Symbol,
+};
+
+/// Clients which don't read their messages for this long are disconnected so
+/// they can't block the bot.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long the client waits for the response to a request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Request of the control API, sent as a single JSON line like
+/// `{"id": 1, "method": "remove_trigger", "index": 0}`.
+///
+/// Triggers are indexed from 0. Every request is answered by a [`Response`] with
+/// the same `id`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Request {
+    ListTriggers,
+    AddTrigger {
+        trigger: PriceTrigger,
+    },
+    RemoveTrigger {
+        index: usize,
+    },
+    SetTriggerEnabled {
+        index: usize,
+        enabled: bool,
+    },
+    Watch {
+        symbol: String,
+    },
+    Unwatch {
+        symbol: String,
+    },
+    /// Shows the prices also in the `currency` or only in their quote assets if `null`.
+    SetCurrency {
+        currency: Option<Currency>,
+    },
+    /// Starts sending the [`Event`]s of the `events` kinds.
+    Subscribe {
+        events: Vec<EventKind>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RequestLine {
+    #[serde(default)]
+    id: Option<u64>,
+    #[serde(flatten)]
+    request: Request,
+}
+
+/// Answer to a [`Request`] with its `result` if it is `ok` or else the `error`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Response {
+    id: Option<u64>,
+    ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Price,
+    Alert,
+    Snapshot,
+}
+
+/// Message sent to the subscribed clients, e.g.
+/// `{"event": "price", "symbol": "ETHUSDT", "price": "1208.5", "change_percent": "-1.2"}`.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// New price of a watched symbol.
+    Price {
+        symbol: Symbol,
+        price: Decimal,
+        change_percent: Decimal,
+    },
+    /// New alert with its text as `message`.
+    Alert { alert: Alert, message: String },
+    /// Everything the TUI shows, sent after every update of the bot.
+    Snapshot { snapshot: Box<BotSnapshot> },
+}
+
+impl Event {
+    fn kind(&self) -> EventKind {
+        match self {
+            Event::Price { .. } => EventKind::Price,
+            Event::Alert { .. } => EventKind::Alert,
+            Event::Snapshot { .. } => EventKind::Snapshot,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ServerMessage {
+    Event(Event),
+    Response(Response),
+}
+
+/// What the connection threads tell the server.
+enum Incoming {
+    Connected(usize, UnixStream),
+    Line(usize, String),
+    Disconnected(usize),
+}
+
+struct Client {
+    stream: UnixStream,
+    events: Vec<EventKind>,
+}
+
+/// Serves the control API on a Unix socket to let other processes, like a TUI
+/// attached to the daemon, read and change the bot.
+///
+/// Clients are read by their own threads while the requests are executed by
+/// [`ControlServer::process`] in the thread owning the bot.
+pub struct ControlServer {
+    path: PathBuf,
+    incoming: Receiver<Incoming>,
+    clients: HashMap<usize, Client>,
+    /// When the price of each symbol was last published.
+    published_prices: HashMap<Symbol, Instant>,
+    /// Number of the alerts of the bot which were already published.
+    published_alerts: usize,
+}
+
+impl ControlServer {
+    /// Listens on the socket at `path`, replacing a socket left by a previous run.
+    ///
+    /// The socket is created in a directory only the owner can access so no other user
+    /// can ever connect to it. The directory is created if it doesn't exist.
+    pub fn bind(path: PathBuf) -> Result<Self> {
+        let context =
+            |err: std::io::Error| Error::from(err).with_context(path.display().to_string());
+        let dir = path.parent().unwrap_or(Path::new("."));
+        DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .map_err(context)?;
+        if fs::metadata(dir).map_err(context)?.permissions().mode() & 0o077 != 0 {
+            return Err(Error::config(format!(
+                "the directory of the socket {} must only be accessible by its owner",
+                path.display()
+            )));
+        }
+        // The PID file already makes sure no other daemon is using the socket
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        let listener = UnixListener::bind(&path).map_err(context)?;
+        let (tx, incoming) = channel();
+        thread::spawn(move || accept_clients(listener, tx));
+        Ok(Self {
+            path,
+            incoming,
+            clients: HashMap::new(),
+            published_prices: HashMap::new(),
+            published_alerts: 0,
+        })
+    }
+
+    /// Executes the requests of the clients received since this was last called.
+    pub fn process(&mut self, bot: &mut Bot) {
+        loop {
+            match self.incoming.try_recv() {
+                Ok(Incoming::Connected(id, stream)) => {
+                    // Only sending fails after the timeout, the socket stays open
+                    let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+                    let client = Client {
+                        stream,
+                        events: Vec::new(),
+                    };
+                    self.clients.insert(id, client);
+                }
+                Ok(Incoming::Line(id, line)) => self.answer(id, &line, bot),
+                Ok(Incoming::Disconnected(id)) => {
+                    self.clients.remove(&id);
+                }
+                Err(_) => break,
+            }
+        }
+    }
+
+    fn answer(&mut self, id: usize, line: &str, bot: &mut Bot) {
+        let mut send_snapshot = false;
+        let response = match serde_json::from_str::<RequestLine>(line) {
+            Ok(RequestLine {
+                id: request_id,
+                request,
+            }) => {
+                if let (Request::Subscribe { events }, Some(client)) =
+                    (&request, self.clients.get_mut(&id))
+                {
+                    client.events = events.clone();
+                    // Attached clients get the current state right away instead of after
+                    // the next update
+                    send_snapshot = events.contains(&EventKind::Snapshot);
+                }
+                response(request_id, execute(request, bot))
+            }
+            Err(err) => response(None, Err(Error::from(err).with_context("invalid request"))),
+        };
+        self.send(id, &ServerMessage::Response(response));
+        if send_snapshot {
+            let snapshot = Box::new(bot.snapshot());
+            self.send(id, &ServerMessage::Event(Event::Snapshot { snapshot }));
+        }
+    }
+
+    /// Sends the new prices, the new alerts and the snapshot to the subscribed clients.
+    pub fn publish(&mut self, bot: &Bot) {
+        let mut events = Vec::new();
+        for tracker in bot.watchlist() {
+            let Some(updated) = tracker.updated() else {
+                continue;
+            };
+            if self.published_prices.insert(tracker.symbol(), updated) != Some(updated) {
+                let stats = tracker.stats();
+                events.push(Event::Price {
+                    symbol: tracker.symbol(),
+                    price: stats.last_price,
+                    change_percent: stats.price_change_percent,
+                });
+            }
+        }
+        let alerts = bot.alerts();
+        for alert in alerts.since(self.published_alerts) {
+            events.push(Event::Alert {
+                alert: alert.clone(),
+                message: alert.to_string(),
+            });
+        }
+        self.published_alerts = alerts.raised();
+        let wants_snapshot = self
+            .clients
+            .values()
+            .any(|client| client.events.contains(&EventKind::Snapshot));
+        if wants_snapshot {
+            let snapshot = Box::new(bot.snapshot());
+            events.push(Event::Snapshot { snapshot });
+        }
+
+        for event in events {
+            let kind = event.kind();
+            let message = ServerMessage::Event(event);
+            let ids = self
+                .clients
+                .iter()
+                .filter(|(_, client)| client.events.contains(&kind))
+                .map(|(&id, _)| id)
+                .collect::<Vec<usize>>();
+            for id in ids {
+                self.send(id, &message);
+            }
+        }
+    }
+
+    /// Forgets what was published about the previous bot after it was replaced.
+    pub fn reset(&mut self) {
+        self.published_prices.clear();
+        self.published_alerts = 0;
+    }
+
+    /// Writes the `message` to the client, disconnecting it if that fails.
+    fn send(&mut self, id: usize, message: &ServerMessage) {
+        let Some(client) = self.clients.get_mut(&id) else {
+            return;
+        };
+        if write_line(&mut client.stream, message).is_err() {
+            self.clients.remove(&id);
+        }
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        // Nothing else can be done if the socket can't be removed
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Accepts the clients until the server is dropped, reading each client in a new thread.
+fn accept_clients(listener: UnixListener, tx: Sender<Incoming>) {
+    for (id, stream) in listener.incoming().enumerate() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let Ok(reader) = stream.try_clone() else {
+            continue;
+        };
+        if tx.send(Incoming::Connected(id, stream)).is_err() {
+            return;
+        }
+        let tx = tx.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if !line.trim().is_empty() && tx.send(Incoming::Line(id, line)).is_err() {
+                    return;
+                }
+            }
+            let _ = tx.send(Incoming::Disconnected(id));
+        });
+    }
+}
+
+fn execute(request: Request, bot: &mut Bot) -> Result<Value> {
+    let triggers = |bot: &Bot| json!(bot.triggers());
+    Ok(match request {
+        Request::ListTriggers => json!({ "triggers": triggers(bot) }),
+        Request::AddTrigger { trigger } => {
+            trigger.validate().map_err(Error::command)?;
+            bot.add_trigger(trigger)?;
+            json!({ "triggers": triggers(bot) })
+        }
+        Request::RemoveTrigger { index } => {
+            let removed = bot.remove_trigger(index);
+            json!({ "removed": removed, "triggers": triggers(bot) })
+        }
+        Request::SetTriggerEnabled { index, enabled } => {
+            let trigger = bot.set_trigger_enabled(index, enabled);
+            json!({ "trigger": trigger, "triggers": triggers(bot) })
+        }
+        Request::Watch { symbol } => json!({ "added": bot.watch(&symbol)? }),
+        Request::Unwatch { symbol } => json!({ "removed": bot.unwatch(&symbol)? }),
+        Request::SetCurrency { currency } => {
+            bot.set_currency(currency)?;
+            json!({ "currency": bot.currency() })
+        }
+        Request::Subscribe { events } => json!({ "events": events }),
+    })
+}
+
+fn response(id: Option<u64>, result: Result<Value>) -> Response {
+    match result {
+        Ok(result) => Response {
+            id,
+            ok: true,
+            result: Some(result),
+            error: None,
+        },
+        Err(err) => Response {
+            id,
+            ok: false,
+            result: None,
+            error: Some(err.to_string()),
+        },
+    }
+}
+
+fn write_line<T: Serialize>(stream: &mut UnixStream, message: &T) -> Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+    Ok(())
+}
+
+/// Bot running in the daemon which is read and changed through the control API.
+///
+/// The TUI attached to the daemon uses it in place of its own [`Bot`].
+pub struct ControlClient {
+    stream: UnixStream,
+    messages: Receiver<Result<ServerMessage>>,
+    /// The latest snapshot sent by the daemon.
+    snapshot: BotSnapshot,
+    /// Exchange info from the cache the daemon shares with the TUI.
+    exchange: Option<Arc<ExchangeInfo>>,
+    next_id: u64,
+    errors: Vec<Error>,
+    disconnected: bool,
+}
+
+impl ControlClient {
+    /// Connects to the daemon listening at `path` and subscribes to its snapshots.
+    pub fn connect(path: &Path) -> Result<Self> {
+        let mut stream = UnixStream::connect(path).map_err(|err| {
+            Error::network(format!(
+                "can't connect to the daemon at {}, is it running? ({err})",
+                path.display()
+            ))
+        })?;
+        let reader = BufReader::new(stream.try_clone()?);
+        let (tx, messages) = channel();
+        thread::spawn(move || {
+            for line in reader.lines() {
+                let message = line
+                    .map_err(Error::from)
+                    .and_then(|line| Ok(serde_json::from_str(&line)?));
+                if tx.send(message).is_err() {
+                    return;
+                }
+            }
+        });
+
+        let subscribe = RequestLine {
+            id: None,
+            request: Request::Subscribe {
+                events: vec![EventKind::Snapshot],
+            },
+        };
+        write_line(&mut stream, &subscribe)?;
+        // The daemon sends its state right after accepting the subscription
+        let snapshot = loop {
+            match messages.recv_timeout(REQUEST_TIMEOUT) {
+                Ok(Ok(ServerMessage::Event(Event::Snapshot { snapshot }))) => break *snapshot,
+                Ok(Ok(ServerMessage::Response(Response {
+                    ok: false, error, ..
+                }))) => return Err(Error::remote(error.unwrap_or_default())),
+                Ok(Ok(_)) => (),
+                Ok(Err(err)) => return Err(err),
+                Err(_) => return Err(Error::network("the daemon didn't send its state")),
+            }
+        };
+
+        Ok(Self {
+            stream,
+            messages,
+            snapshot,
+            exchange: exchange::load_cache().map(Arc::new),
+            next_id: 0,
+            errors: Vec::new(),
+            disconnected: false,
+        })
+    }
+
+    /// Sends the `request` and waits for its result.
+    fn request(&mut self, request: Request) -> Result<Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        write_line(
+            &mut self.stream,
+            &RequestLine {
+                id: Some(id),
+                request,
+            },
+        )?;
+
+        let deadline = Instant::now() + REQUEST_TIMEOUT;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match self.messages.recv_timeout(timeout) {
+                Ok(Ok(ServerMessage::Response(response))) if response.id == Some(id) => {
+                    return match response.ok {
+                        true => Ok(response.result.unwrap_or(Value::Null)),
+                        false => Err(Error::remote(response.error.unwrap_or_default())),
+                    };
+                }
+                Ok(Ok(ServerMessage::Event(Event::Snapshot { snapshot }))) => {
+                    self.snapshot = *snapshot;
+                }
+                Ok(Ok(_)) => (),
+                Ok(Err(err)) => return Err(err),
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(Error::network("the daemon didn't answer in time"))
+                }
+                Err(RecvTimeoutError::Disconnected) => return Err(lost_connection()),
+            }
+        }
+    }
+
+    /// Stores the triggers sent back by a request changing them.
+    fn update_triggers(&mut self, result: &Value) -> Result<()> {
+        self.snapshot.triggers = serde_json::from_value(result["triggers"].clone())?;
+        Ok(())
+    }
+}
+
+fn lost_connection() -> Error {
+    Error::network("lost the connection to the daemon")
+}
+
+impl BotControl for ControlClient {
+    fn update(&mut self) {
+        loop {
+            match self.messages.try_recv() {
+                Ok(Ok(ServerMessage::Event(Event::Snapshot { snapshot }))) => {
+                    self.snapshot = *snapshot;
+                }
+                Ok(Ok(_)) => (),
+                Ok(Err(err)) => self.errors.push(err),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if !std::mem::replace(&mut self.disconnected, true) {
+                        self.errors.push(lost_connection());
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    fn snapshot(&self) -> BotSnapshot {
+        self.snapshot.clone()
+    }
+
+    fn take_errors(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn add_trigger(&mut self, trigger: PriceTrigger) -> Result<()> {
+        let result = self.request(Request::AddTrigger { trigger })?;
+        self.update_triggers(&result)
+    }
+
+    fn remove_trigger(&mut self, index: usize) -> Result<Option<PriceTrigger>> {
+        let result = self.request(Request::RemoveTrigger { index })?;
+        self.update_triggers(&result)?;
+        Ok(serde_json::from_value(result["removed"].clone())?)
+    }
+
+    fn set_trigger_enabled(&mut self, index: usize, enabled: bool) -> Result<Option<PriceTrigger>> {
+        let result = self.request(Request::SetTriggerEnabled { index, enabled })?;
+        self.update_triggers(&result)?;
+        Ok(serde_json::from_value(result["trigger"].clone())?)
+    }
+
+    fn watch(&mut self, name: &str) -> Result<bool> {
+        let symbol = name.to_owned();
+        let result = self.request(Request::Watch { symbol })?;
+        Ok(result["added"].as_bool().unwrap_or(false))
+    }
+
+    fn unwatch(&mut self, name: &str) -> Result<bool> {
+        let symbol = name.to_owned();
+        let result = self.request(Request::Unwatch { symbol })?;
+        Ok(result["removed"].as_bool().unwrap_or(false))
+    }
+
+    fn set_currency(&mut self, currency: Option<Currency>) -> Result<()> {
+        self.request(Request::SetCurrency { currency })?;
+        Ok(())
+    }
+
+    fn exchange_info(&self) -> Option<Arc<ExchangeInfo>> {
+        self.exchange.clone()
+    }
+
+    /// The daemon saves its own state.
+    fn save_data(&self) -> Option<SaveData> {
+        None
+    }
+
+    fn shutdown(&mut self, _timeout: Duration) {
+        // The daemon notices the client is gone either way
+        let _ = self.stream.shutdown(std::net::Shutdown::Both);
+    }
+}
+
+#[test]
+fn protocol_messages() {
+    let line = r#"{"id": 3, "method": "set_trigger_enabled", "index": 1, "enabled": false}"#;
+    let request: RequestLine = serde_json::from_str(line).unwrap();
+    assert_eq!(request.id, Some(3));
+    assert!(matches!(
+        request.request,
+        Request::SetTriggerEnabled {
+            index: 1,
+            enabled: false
+        }
+    ));
+    assert!(serde_json::from_str::<RequestLine>(r#"{"method": "launch"}"#).is_err());
+
+    let response = response(Some(3), Err(Error::command("no trigger 2")));
+    let json = serde_json::to_string(&ServerMessage::Response(response)).unwrap();
+    assert_eq!(
+        json,
+        r#"{"id":3,"ok":false,"error":"Command Error: no trigger 2"}"#
+    );
+    assert!(matches!(
+        serde_json::from_str(&json).unwrap(),
+        ServerMessage::Response(Response { ok: false, .. })
+    ));
+
+    let event = ServerMessage::Event(Event::Price {
+        symbol: Symbol::new("ETHUSDT").unwrap(),
+        price: Decimal::new(12085, 1),
+        change_percent: Decimal::new(-12, 1),
+    });
+    let json = serde_json::to_string(&event).unwrap();
+    assert!(json.starts_with(r#"{"event":"price","symbol":"ETHUSDT""#));
+    assert!(matches!(
+        serde_json::from_str(&json).unwrap(),
+        ServerMessage::Event(Event::Price { .. })
+    ));
+}
@@ -0,0 +1,98 @@
+use std::{
+    backtrace::Backtrace,
+    collections::VecDeque,
+    fs,
+    panic::{self, PanicHookInfo},
+    path::PathBuf,
+    sync::Mutex,
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{error::Result, save, terminal};
+
+/// Number of the last log lines written to a crash report.
+const RECENT_LOG_LINES: usize = 50;
+
+/// The last lines of the Log panel which are kept for the crash report.
+static RECENT_LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Keeps the log `line` for the crash report.
+pub fn remember_log_line(line: String) {
+    if let Ok(mut log) = RECENT_LOG.lock() {
+        if log.len() == RECENT_LOG_LINES {
+            log.pop_front();
+        }
+        log.push_back(line);
+    }
+}
+
+/// Makes a panic of the main thread restore the terminal, write the pending
+/// save and a crash report before the panic message is printed.
+///
+/// Panics of the reader threads are ignored because the threads are restarted
+/// and printing the message would break the TUI.
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().name() != Some("main") {
+            return;
+        }
+        terminal::restore();
+        if let Err(err) = save::try_flush() {
+            eprintln!("Failed to save the bot data: {err}");
+        }
+        match write_crash_report(info) {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(err) => eprintln!("Failed to write the crash report: {err}"),
+        }
+        default_hook(info);
+    }));
+}
+
+/// Writes the panic, its backtrace and the recent log lines into a new file
+/// next to the executable.
+fn write_crash_report(info: &PanicHookInfo) -> Result<PathBuf> {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = std::env::current_exe()?.with_file_name(format!("crash-{time}.txt"));
+    // The log is skipped if the panic happened while it was being written
+    let log = match RECENT_LOG.try_lock() {
+        Ok(log) => log.iter().cloned().collect(),
+        Err(_) => Vec::new(),
+    };
+    let report = crash_report(
+        &info.to_string(),
+        &Backtrace::force_capture().to_string(),
+        &log,
+    );
+    fs::write(&path, report)?;
+    Ok(path)
+}
+
+fn crash_report(panic: &str, backtrace: &str, log: &[String]) -> String {
+    let mut report = format!(
+        "Crypto Alertabot {} crashed\n\n{panic}\n\nBacktrace:\n{backtrace}\n\nRecent log:\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    if log.is_empty() {
+        report += "(empty)\n";
+    }
+    for line in log {
+        report += line;
+        report.push('\n');
+    }
+    report
+}
+
+#[test]
+fn crash_report_contents() {
+    let log = vec!["12:00:00 first".to_owned(), "12:00:01 second".to_owned()];
+    let report = crash_report("panicked at src/input.rs:155:13", "0: main", &log);
+    assert!(report.contains("panicked at src/input.rs:155:13"));
+    assert!(report.contains("Backtrace:\n0: main"));
+    assert!(report.ends_with("12:00:00 first\n12:00:01 second\n"));
+    assert!(crash_report("", "", &[]).ends_with("(empty)\n"));
+}
@@ -0,0 +1,217 @@
+use std::{fmt::Display, str::FromStr, sync::Arc};
+
+use crate::{
+    decimal::Decimal,
+    error::{Error, Result},
+    exchange::ExchangeInfo,
+    format::Precision,
+    symbol::Symbol,
+};
+
+/// Assets tried as the middle step when two assets aren't traded against each other.
+const BRIDGE_ASSETS: &[&str] = &["USDT", "BTC", "ETH", "BNB", "FDUSD", "USDC"];
+
+/// Fiat currencies and stablecoins which are shown with 2 decimals.
+const FIAT_CURRENCIES: &[&str] = &[
+    "USD", "USDT", "FDUSD", "BUSD", "USDC", "TUSD", "USDP", "DAI", "EUR", "GBP", "TRY", "BRL",
+    "AUD", "JPY", "RUB", "UAH", "ZAR", "PLN", "RON", "ARS", "NGN",
+];
+
+/// Longest currency name accepted.
+const MAX_LENGTH: usize = 10;
+
+/// Currency the prices are shown or compared in, like `EUR` or `BTC`.
+///
+/// Binance has no USD markets so `USD` stands for `USDT`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Currency(Arc<str>);
+
+impl Currency {
+    /// Creates a currency from user input, e.g. `eur` becomes `EUR`.
+    pub fn new(name: &str) -> Result<Currency> {
+        let name = name.trim().to_uppercase();
+        if name.is_empty()
+            || name.len() > MAX_LENGTH
+            || !name.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            return Err(Error::invalid_currency(name));
+        }
+        Ok(Currency(name.into()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the asset the currency is traded as on Binance.
+    pub fn asset(&self) -> &str {
+        match &*self.0 {
+            "USD" => "USDT",
+            name => name,
+        }
+    }
+
+    /// Precision of the prices converted into the currency.
+    pub fn precision(&self) -> Precision {
+        match FIAT_CURRENCIES.contains(&&*self.0) {
+            true => Precision::decimals(2),
+            false => Precision::default(),
+        }
+    }
+}
+
+impl Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for Currency {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Currency::new(s)
+    }
+}
+
+impl serde::Serialize for Currency {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Currency {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Currency::new(&name).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Symbol whose price is one step of a [`Conversion`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Leg {
+    symbol: Symbol,
+    /// Whether the step goes from the quote to the base asset so the price is divided by.
+    inverse: bool,
+}
+
+/// Pairs whose prices convert one asset into another, e.g. `BTC` into `EUR` through
+/// `BTCUSDT` and `EURUSDT` when there is no direct pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conversion {
+    legs: Vec<Leg>,
+}
+
+impl Conversion {
+    /// Finds the traded pairs converting the asset `from` into `to`, trying a direct
+    /// pair first and then going through one of the [`BRIDGE_ASSETS`].
+    pub fn find(info: &ExchangeInfo, from: &str, to: &str) -> Option<Self> {
+        if from == to {
+            return Some(Self { legs: Vec::new() });
+        }
+        if let Some(leg) = Self::leg(info, from, to) {
+            return Some(Self { legs: vec![leg] });
+        }
+        BRIDGE_ASSETS
+            .iter()
+            .filter(|&&bridge| bridge != from && bridge != to)
+            .find_map(|bridge| {
+                let legs = vec![Self::leg(info, from, bridge)?, Self::leg(info, bridge, to)?];
+                Some(Self { legs })
+            })
+    }
+
+    /// Returns the traded pair of the two assets in either direction.
+    fn leg(info: &ExchangeInfo, from: &str, to: &str) -> Option<Leg> {
+        let traded = |base: &str, quote: &str| {
+            info.get(&format!("{base}{quote}"))
+                .filter(|info| info.is_trading())
+                .filter(|info| info.base_asset == base && info.quote_asset == quote)
+                .and_then(|info| Symbol::new(&info.symbol).ok())
+        };
+        match traded(from, to) {
+            Some(symbol) => Some(Leg {
+                symbol,
+                inverse: false,
+            }),
+            None => traded(to, from).map(|symbol| Leg {
+                symbol,
+                inverse: true,
+            }),
+        }
+    }
+
+    /// Returns the symbols whose prices are needed.
+    pub fn symbols(&self) -> impl Iterator<Item = &Symbol> {
+        self.legs.iter().map(|leg| &leg.symbol)
+    }
+
+    /// Computes how much of the target asset one unit is worth from the `price`s of
+    /// the pairs, or returns `None` while one of them isn't known.
+    pub fn rate(&self, price: impl Fn(&Symbol) -> Option<Decimal>) -> Option<Decimal> {
+        self.legs.iter().try_fold(Decimal::ONE, |rate, leg| {
+            let price = price(&leg.symbol)?;
+            match leg.inverse {
+                true => rate.checked_div(price),
+                false => rate.checked_mul(price),
+            }
+        })
+    }
+}
+
+#[test]
+fn currency_conversions() {
+    use crate::exchange::SymbolInfo;
+
+    let pair = |base: &str, quote: &str| SymbolInfo {
+        symbol: format!("{base}{quote}"),
+        status: "TRADING".to_owned(),
+        base_asset: base.to_owned(),
+        quote_asset: quote.to_owned(),
+        tick_size: None,
+        lot_size: None,
+    };
+    let mut symbols = vec![
+        pair("BTC", "USDT"),
+        pair("ETH", "USDT"),
+        pair("ETH", "BTC"),
+        pair("EUR", "USDT"),
+    ];
+    symbols.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+    let info = ExchangeInfo {
+        fetched_at: 0,
+        symbols,
+    };
+    let prices = |symbol: &Symbol| match symbol.as_str() {
+        "BTCUSDT" => "20000".parse().ok(),
+        "ETHUSDT" => "1500".parse().ok(),
+        "EURUSDT" => "1.25".parse().ok(),
+        _ => None,
+    };
+    let rate = |from: &str, to: &str| Conversion::find(&info, from, to)?.rate(prices);
+
+    let usd = Currency::new(" usd").unwrap();
+    assert_eq!(usd.asset(), "USDT");
+    assert_eq!(usd.precision(), Precision::decimals(2));
+    assert!(Currency::new("EUR/USD").is_err());
+    assert_eq!(rate("USDT", usd.asset()), Some(Decimal::ONE));
+
+    // ETH is quoted in USDT and USDT converted to EUR through EURUSDT
+    assert_eq!(rate("ETH", "USDT"), "1500".parse().ok());
+    assert_eq!(rate("USDT", "EUR"), "0.8".parse().ok());
+    // There is no EUR pair of BTC so it goes through USDT
+    let btc_eur = Conversion::find(&info, "BTC", "EUR").unwrap();
+    assert_eq!(
+        btc_eur.symbols().map(Symbol::as_str).collect::<Vec<&str>>(),
+        ["BTCUSDT", "EURUSDT"]
+    );
+    assert_eq!(btc_eur.rate(prices), "16000".parse().ok());
+    // ETHBTC is known but its price isn't read yet
+    assert_eq!(rate("ETH", "BTC"), None);
+    assert!(Conversion::find(&info, "DOGE", "EUR").is_none());
+}
@@ -0,0 +1,322 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, LineWriter, Write},
+    path::PathBuf,
+    process, thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(unix)]
+use crate::control::ControlServer;
+use crate::{
+    bot::{Alert, Bot},
+    cli::Cli,
+    config::{self, Config},
+    connection::ConnectionState,
+    crash,
+    error::{Error, Result},
+    http::HttpServer,
+    save,
+    signals::{ReloadSignal, ShutdownSignal},
+    sinks::{self, AlertSinks, SinkConfig},
+};
+
+const LOG_FILE: &str = "crypto-alertabot.log";
+const PID_FILE: &str = "crypto-alertabot.pid";
+const SOCKET: &str = "crypto-alertabot-control/crypto-alertabot.sock";
+
+/// Files of the daemon, configured in the `[daemon]` section of the config file.
+///
+/// Relative paths and the defaults are next to the executable like the save file.
+#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DaemonConfig {
+    /// File the alerts, the errors and the connection changes are appended to.
+    pub log_file: Option<PathBuf>,
+    /// File holding the process ID while the daemon runs.
+    pub pid_file: Option<PathBuf>,
+    /// Unix socket of the control API in a directory only the owner can access, see
+    /// [`crate::control`].
+    pub socket: Option<PathBuf>,
+}
+
+impl DaemonConfig {
+    fn log_file(&self) -> Result<PathBuf> {
+        next_to_executable(self.log_file.as_ref(), LOG_FILE)
+    }
+
+    fn pid_file(&self) -> Result<PathBuf> {
+        next_to_executable(self.pid_file.as_ref(), PID_FILE)
+    }
+
+    pub fn socket(&self) -> Result<PathBuf> {
+        next_to_executable(self.socket.as_ref(), SOCKET)
+    }
+}
+
+fn next_to_executable(path: Option<&PathBuf>, default: &str) -> Result<PathBuf> {
+    let exe = std::env::current_exe()?;
+    Ok(match path {
+        Some(path) if path.is_absolute() => path.clone(),
+        Some(path) => exe.with_file_name(path),
+        None => exe.with_file_name(default),
+    })
+}
+
+/// Runs the bot without the TUI until SIGINT or SIGTERM is received, e.g. on a server.
+///
+/// The daemon stays in the foreground so it can be run by a service manager. It
+/// shares the config and the save file with the TUI so the triggers can be edited
+/// by the `triggers` subcommands followed by SIGHUP, which reloads both. The `cli`
+/// is kept to load the config again in the same way.
+pub fn run(config: Config, cli: &Cli) -> Result<()> {
+    config.validate()?;
+    let _pid_file = PidFile::create(config.daemon.pid_file()?)?;
+    crash::install_panic_hook();
+    let shutdown_signal = ShutdownSignal::register_daemon()?;
+    let reload_signal = ReloadSignal::register()?;
+
+    let mut daemon = Daemon::new(config)?;
+    daemon
+        .log
+        .info(format!("Started with the process ID {}", process::id()));
+
+    let result = daemon.run(cli, &shutdown_signal, &reload_signal);
+    if let Err(err) = &result {
+        daemon.log.error(err);
+    }
+    // The bot is shut down even if the main loop failed
+    let shutdown_result = daemon.shutdown();
+    result.and(shutdown_result)
+}
+
+struct Daemon {
+    config: Config,
+    bot: Bot,
+    sinks: AlertSinks,
+    log: DaemonLog,
+    /// Number of the alerts of the bot which were already delivered.
+    delivered_alerts: usize,
+    connection: ConnectionState,
+    #[cfg(unix)]
+    control: ControlServer,
+    /// Server of the JSON API if it is configured.
+    http: Option<HttpServer>,
+}
+
+impl Daemon {
+    fn new(config: Config) -> Result<Self> {
+        let log = DaemonLog::open(config.daemon.log_file()?)?;
+        let sinks = AlertSinks::new(&daemon_sinks(&config.sinks))?;
+        let bot = config.create_bot(save::load_save_file()?)?;
+        Ok(Self {
+            #[cfg(unix)]
+            control: ControlServer::bind(config.daemon.socket()?)?,
+            http: config
+                .http
+                .address()
+                .as_deref()
+                .map(HttpServer::bind)
+                .transpose()?,
+            config,
+            bot,
+            sinks,
+            log,
+            delivered_alerts: 0,
+            connection: ConnectionState::default(),
+        })
+    }
+
+    fn run(
+        &mut self,
+        cli: &Cli,
+        shutdown_signal: &ShutdownSignal,
+        reload_signal: &ReloadSignal,
+    ) -> Result<()> {
+        const STEP: Duration = Duration::from_millis(100);
+
+        let mut last = Instant::now();
+        let mut last_save = Instant::now();
+        while !shutdown_signal.received() {
+            if reload_signal.take() {
+                match self.reload(cli) {
+                    Ok(()) => self.log.info("Reloaded the config and the saved state"),
+                    // The daemon keeps running with the old config
+                    Err(err) => self.log.error(&err.with_context("reload")),
+                }
+            }
+
+            // The requests are answered more often than the bot is updated
+            #[cfg(unix)]
+            self.control.process(&mut self.bot);
+            if let Some(http) = &mut self.http {
+                http.process(&mut self.bot);
+            }
+
+            if last.elapsed() >= self.config.poll_interval() {
+                last = Instant::now();
+                self.tick()?;
+            }
+
+            if last_save.elapsed() >= crate::SAVE_INTERVAL {
+                last_save = Instant::now();
+                save::flush()?;
+            }
+            thread::sleep(STEP);
+        }
+        self.log.info("Received a shutdown signal -> shutting down");
+        Ok(())
+    }
+
+    fn tick(&mut self) -> Result<()> {
+        self.bot.update();
+        self.deliver_alerts();
+        for error in self.bot.take_errors() {
+            self.log.error(&error);
+        }
+        let connection = self.bot.connection();
+        if connection != self.connection {
+            self.log
+                .info(format!("Connection to Binance is {connection}"));
+            self.connection = connection;
+        }
+        #[cfg(unix)]
+        self.control.publish(&self.bot);
+        if let Some(http) = &mut self.http {
+            http.publish(&self.bot);
+        }
+        save::schedule(&self.bot.save_data())
+    }
+
+    /// Logs the new alerts and sends them to the alert sinks.
+    fn deliver_alerts(&mut self) {
+        let alerts = self.bot.alerts();
+        let new_alerts = alerts.since(self.delivered_alerts).collect::<Vec<&Alert>>();
+        self.delivered_alerts = alerts.raised();
+        for alert in new_alerts {
+            self.log.write("ALERT", &alert.to_string());
+            self.sinks.deliver(alert);
+        }
+        if let Err(err) = self.sinks.flush() {
+            self.log.error(&err.with_context("alert sinks"));
+        }
+    }
+
+    /// Replaces the bot, the sinks and the log by new ones from the config and
+    /// the save file which are loaded again.
+    ///
+    /// Nothing is replaced if anything fails to load. The control API and the HTTP
+    /// server keep their addresses and clients.
+    fn reload(&mut self, cli: &Cli) -> Result<()> {
+        // The changes made through the control API or HTTP which weren't saved yet
+        // are written first so loading the save file doesn't lose them
+        save::schedule(&self.bot.save_data())?;
+        save::flush()?;
+        let (config, _) = config::load_config(cli.config.as_deref(), &cli.overrides)?;
+        config.validate()?;
+        // Reopened so rotated log files are let go
+        let log = DaemonLog::open(config.daemon.log_file()?)?;
+        let sinks = AlertSinks::new(&daemon_sinks(&config.sinks))?;
+        let bot = config.create_bot(save::load_save_file()?)?;
+
+        self.bot.shutdown(crate::SHUTDOWN_TIMEOUT);
+        self.deliver_alerts();
+        self.config = config;
+        self.bot = bot;
+        self.sinks = sinks;
+        self.log = log;
+        self.delivered_alerts = 0;
+        #[cfg(unix)]
+        self.control.reset();
+        if let Some(http) = &mut self.http {
+            http.reset();
+        }
+        // The loaded state replaces the one of the old bot waiting to be saved
+        save::schedule(&self.bot.save_data())
+    }
+
+    /// Stops the reader threads, delivers the remaining alerts and saves the state.
+    fn shutdown(&mut self) -> Result<()> {
+        self.bot.shutdown(crate::SHUTDOWN_TIMEOUT);
+        self.deliver_alerts();
+        save::schedule(&self.bot.save_data())?;
+        save::flush()?;
+        self.log.info("Stopped");
+        Ok(())
+    }
+}
+
+/// The alert sinks of the daemon which has no terminal to ring the bell in.
+fn daemon_sinks(config: &SinkConfig) -> SinkConfig {
+    SinkConfig {
+        bell: false,
+        ..config.clone()
+    }
+}
+
+/// Log of the daemon with a line for every message, prefixed by its time and level.
+struct DaemonLog(LineWriter<File>);
+
+impl DaemonLog {
+    fn open(path: PathBuf) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|err| Error::from(err).with_context(path.display().to_string()))?;
+        Ok(Self(LineWriter::new(file)))
+    }
+
+    fn info<S: AsRef<str>>(&mut self, message: S) {
+        self.write("INFO", message.as_ref());
+    }
+
+    fn error(&mut self, error: &Error) {
+        self.write("ERROR", &error.to_string());
+    }
+
+    fn write(&mut self, level: &str, message: &str) {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let line = format!("{} {level:<5} {message}", sinks::format_timestamp(seconds));
+        // The log is the only place to report its own errors so they go to stderr
+        if let Err(err) = writeln!(self.0, "{line}") {
+            eprintln!("Failed to write the log: {err}");
+        }
+        crash::remember_log_line(line);
+    }
+}
+
+/// PID file which is removed when dropped.
+struct PidFile(PathBuf);
+
+impl PidFile {
+    /// Writes the ID of this process into a new file at `path`.
+    ///
+    /// Fails if the file exists as another daemon may be running.
+    fn create(path: PathBuf) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::AlreadyExists => Error::config(format!(
+                    "PID file {} exists, the daemon may be running already or it crashed \
+                    and the file can be removed",
+                    path.display()
+                )),
+                _ => Error::from(err).with_context(path.display().to_string()),
+            })?;
+        writeln!(file, "{}", process::id())?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Nothing else can be done if the file can't be removed
+        let _ = fs::remove_file(&self.0);
+    }
+}
@@ -0,0 +1,379 @@
+use std::{cmp::Ordering, fmt::Display, ops::Neg, str::FromStr};
+
+/// Exact decimal number like `1209.37` used for the prices, volumes and trigger
+/// levels, so they are compared exactly and shown the same way Binance sent them
+/// or the user typed them.
+///
+/// The number is kept as an integer of all its digits and the number of the
+/// digits after the decimal point, without trailing zeros. Results with more than
+/// [`Decimal::MAX_SCALE`] decimals are rounded, and results which don't fit into
+/// the integer are computed with `f64` instead. The operations return `None` when
+/// even that result can't be kept.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    /// Most digits after the decimal point, Binance uses at most 8.
+    pub const MAX_SCALE: u32 = 18;
+    pub const ZERO: Decimal = Decimal::new(0, 0);
+    pub const ONE: Decimal = Decimal::new(1, 0);
+
+    /// Creates the number `mantissa * 10^-scale`, e.g. `Decimal::new(12093, 1)` is `1209.3`.
+    pub const fn new(mantissa: i128, scale: u32) -> Self {
+        let (mut mantissa, mut scale) = (mantissa, scale);
+        while scale > 0 && mantissa % 10 == 0 {
+            mantissa /= 10;
+            scale -= 1;
+        }
+        Self { mantissa, scale }
+    }
+
+    /// Converts the shortest text of the `value` which reads back as the same `f64`,
+    /// so `0.1` becomes exactly `0.1`, rounding it to [`Self::MAX_SCALE`] decimals if
+    /// it has more. Returns `None` for infinities, NaN and values which don't fit.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        // Display of f64 never uses an exponent
+        value
+            .to_string()
+            .parse()
+            .ok()
+            .or_else(|| Self::round_f64(value, Self::MAX_SCALE))
+    }
+
+    /// Rounds the `value` to `scale` decimals, at most [`Self::MAX_SCALE`], or
+    /// returns `None` for infinities, NaN and values which don't fit.
+    pub fn round_f64(value: f64, scale: u32) -> Option<Self> {
+        let scale = scale.min(Self::MAX_SCALE);
+        let mantissa = (value * 10f64.powi(scale as i32)).round();
+        // i128::MAX as f64 rounds up to 2^127 which doesn't fit
+        let fits = mantissa.is_finite() && mantissa.abs() < i128::MAX as f64;
+        fits.then(|| Self::new(mantissa as i128, scale))
+    }
+
+    /// Number of the digits after the decimal point, without the trailing zeros.
+    pub fn scale(self) -> u32 {
+        self.scale
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    /// Returns the mantissa with `scale` digits after the decimal point or `None`
+    /// if it doesn't fit.
+    fn rescale(self, scale: u32) -> Option<i128> {
+        10i128
+            .checked_pow(scale - self.scale)
+            .and_then(|factor| self.mantissa.checked_mul(factor))
+    }
+
+    /// Rounds the `mantissa` with `scale` decimals half away from zero to at most
+    /// [`Self::MAX_SCALE`] decimals.
+    fn rounded(mantissa: i128, scale: u32) -> Self {
+        Self::rounded_to(mantissa, scale, Self::MAX_SCALE)
+    }
+
+    /// Rounds the `mantissa` with `scale` decimals half away from zero to at most
+    /// `max_scale` decimals.
+    fn rounded_to(mantissa: i128, scale: u32, max_scale: u32) -> Self {
+        if scale <= max_scale {
+            return Self::new(mantissa, scale);
+        }
+        let factor = 10i128.pow(scale - max_scale);
+        let (quotient, remainder) = (mantissa / factor, mantissa % factor);
+        let carry = match remainder.unsigned_abs() * 2 >= factor.unsigned_abs() {
+            true => mantissa.signum(),
+            false => 0,
+        };
+        Self::new(quotient + carry, max_scale)
+    }
+
+    /// Adds `other` or returns `None` if the sum can't be kept.
+    pub fn checked_add(self, other: Decimal) -> Option<Decimal> {
+        let scale = self.scale.max(other.scale);
+        match self
+            .rescale(scale)
+            .zip(other.rescale(scale))
+            .and_then(|(a, b)| a.checked_add(b))
+        {
+            Some(mantissa) => Some(Decimal::new(mantissa, scale)),
+            None => Self::approximate(self.to_f64() + other.to_f64()),
+        }
+    }
+
+    /// Subtracts `other` or returns `None` if the difference can't be kept.
+    pub fn checked_sub(self, other: Decimal) -> Option<Decimal> {
+        self.checked_add(-other)
+    }
+
+    /// Multiplies by `other` rounding to [`Self::MAX_SCALE`] decimals, or returns
+    /// `None` if the product can't be kept.
+    pub fn checked_mul(self, other: Decimal) -> Option<Decimal> {
+        match self.mantissa.checked_mul(other.mantissa) {
+            Some(mantissa) => Some(Self::rounded(mantissa, self.scale + other.scale)),
+            None => Self::approximate(self.to_f64() * other.to_f64()),
+        }
+    }
+
+    /// Divides by `other` rounding to [`Self::MAX_SCALE`] decimals, or returns
+    /// `None` if `other` is zero or the quotient can't be kept.
+    pub fn checked_div(self, other: Decimal) -> Option<Decimal> {
+        self.checked_div_rounded(other, Self::MAX_SCALE)
+    }
+
+    /// Divides by `other` rounding once to `scale` decimals, at most
+    /// [`Self::MAX_SCALE`], or returns `None` if `other` is zero or the quotient
+    /// can't be kept.
+    pub fn checked_div_rounded(self, other: Decimal, scale: u32) -> Option<Decimal> {
+        if other.mantissa == 0 {
+            return None;
+        }
+        // One more decimal than kept so the rounding sees the next digit
+        let scale = scale.min(Self::MAX_SCALE) + 1;
+        let shift = (scale + other.scale) as i32 - self.scale as i32;
+        let operands = match shift >= 0 {
+            true => 10i128
+                .checked_pow(shift as u32)
+                .and_then(|factor| self.mantissa.checked_mul(factor))
+                .map(|dividend| (dividend, other.mantissa)),
+            false => 10i128
+                .checked_pow(shift.unsigned_abs())
+                .and_then(|factor| other.mantissa.checked_mul(factor))
+                .map(|divisor| (self.mantissa, divisor)),
+        };
+        match operands {
+            Some((dividend, divisor)) => {
+                Some(Self::rounded_to(dividend / divisor, scale, scale - 1))
+            }
+            None => {
+                let quotient = self.to_f64() / other.to_f64();
+                Self::round_f64(quotient, scale - 1)
+                    .filter(|result| *result != Self::ZERO || quotient == 0.0)
+            }
+        }
+    }
+
+    /// Keeps the result of an operation which overflowed computed with `f64`, or
+    /// returns `None` if it doesn't fit either or is too small to be kept as
+    /// anything but zero.
+    fn approximate(value: f64) -> Option<Self> {
+        Self::from_f64(value).filter(|result| *result != Self::ZERO || value == 0.0)
+    }
+}
+
+impl From<Decimal> for f64 {
+    fn from(value: Decimal) -> f64 {
+        value.to_f64()
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Decimal;
+
+    fn neg(self) -> Decimal {
+        Decimal::new(-self.mantissa, self.scale)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        // Both are normalised so equal numbers have the same digits
+        self.mantissa == other.mantissa && self.scale == other.scale
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        match (self.rescale(scale), other.rescale(scale)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            // Only numbers with nearly 38 digits don't fit
+            _ => self.to_f64().total_cmp(&other.to_f64()),
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{sign}{digits}");
+        }
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{sign}{integer}.{fraction}")
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid number '{s}'");
+        let (negative, unsigned) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        if integer.is_empty() && fraction.is_empty() {
+            return Err(invalid());
+        }
+        if fraction.len() > Self::MAX_SCALE as usize {
+            return Err(format!("'{s}' has more than {} decimals", Self::MAX_SCALE));
+        }
+        let mut mantissa: i128 = 0;
+        for c in integer.chars().chain(fraction.chars()) {
+            let digit = c.to_digit(10).ok_or_else(invalid)?;
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add(digit as i128))
+                .ok_or_else(invalid)?;
+        }
+        if negative {
+            mantissa = -mantissa;
+        }
+        Ok(Self::new(mantissa, fraction.len() as u32))
+    }
+}
+
+impl serde::Serialize for Decimal {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Decimals are written as strings to keep every digit but numbers are read too,
+/// e.g. from older save files or from the API clients.
+impl<'de> serde::Deserialize<'de> for Decimal {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl serde::de::Visitor<'_> for Visitor {
+            type Value = Decimal;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a decimal number or a string with one")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Decimal, E> {
+                v.parse().map_err(E::custom)
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Decimal, E> {
+                Ok(Decimal::new(v.into(), 0))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Decimal, E> {
+                Ok(Decimal::new(v.into(), 0))
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<Decimal, E> {
+                Decimal::from_f64(v).ok_or_else(|| E::custom(format!("invalid number {v}")))
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+#[test]
+fn decimal_round_trip() {
+    let parse = |s: &str| s.parse::<Decimal>().unwrap();
+    assert_eq!(parse("1209.370").to_string(), "1209.37");
+    assert_eq!(parse("-0.05").to_string(), "-0.05");
+    assert_eq!(parse(".5").to_string(), "0.5");
+    assert_eq!(parse("1210.").to_string(), "1210");
+    assert_eq!(parse("0.00000001"), Decimal::new(1, 8));
+    assert!("1.2.3".parse::<Decimal>().is_err());
+    assert!("".parse::<Decimal>().is_err());
+    assert!("1e5".parse::<Decimal>().is_err());
+
+    assert_eq!(parse("0.1"), Decimal::new(1, 1));
+    assert_eq!(parse("1210.0"), parse("1210"));
+    assert!(parse("0.3") > parse("0.29999999"));
+    assert!(parse("-2") < parse("-1.5"));
+    assert_eq!(
+        Decimal::from_f64(0.1 + 0.2).unwrap().to_string(),
+        "0.30000000000000004"
+    );
+    assert_eq!(Decimal::from_f64(1e-7).unwrap().to_string(), "0.0000001");
+    // The shortest text has more than 18 decimals
+    assert_eq!(
+        Decimal::from_f64(0.0012345678901234567),
+        Some(parse("0.001234567890123457"))
+    );
+    assert_eq!(Decimal::from_f64(f64::NAN), None);
+    assert_eq!(Decimal::from_f64(1e40), None);
+    assert_eq!(Decimal::round_f64(12.345678, 2), Some(parse("12.35")));
+    assert_eq!(Decimal::round_f64(-0.125, 2), Some(parse("-0.13")));
+
+    assert_eq!(parse("0.1").checked_add(parse("0.2")), Some(parse("0.3")));
+    assert_eq!(
+        parse("1209.37").checked_sub(parse("1209.4")),
+        Some(parse("-0.03"))
+    );
+    assert_eq!(
+        parse("1209.37").checked_mul(parse("0.5")),
+        Some(parse("604.685"))
+    );
+    assert_eq!(
+        parse("0.000000001").checked_mul(parse("0.0000000015")),
+        Some(parse("0.000000000000000002"))
+    );
+    // Overflows fall back to f64 but aren't turned into zero
+    let huge = Decimal::new(10i128.pow(30), 0);
+    assert_eq!(
+        huge.checked_mul(Decimal::new(5, 0)),
+        Some(Decimal::new(5 * 10i128.pow(30), 0))
+    );
+    assert_eq!(huge.checked_mul(huge), None);
+    assert_eq!(huge.checked_div(Decimal::new(1, 18)), None);
+    assert_eq!(Decimal::approximate(1e-30), None);
+    assert_eq!(-parse("2.5"), parse("-2.5"));
+    assert_eq!(parse("1500").checked_div(parse("0.5")), Some(parse("3000")));
+    assert_eq!(
+        Decimal::ONE.checked_div(parse("3")),
+        Some(parse("0.333333333333333333"))
+    );
+    assert_eq!(
+        parse("2").checked_div(parse("3")),
+        Some(parse("0.666666666666666667"))
+    );
+    assert_eq!(parse("1").checked_div(Decimal::ZERO), None);
+    assert_eq!(
+        parse("2").checked_div_rounded(parse("3"), 2),
+        Some(parse("0.67"))
+    );
+    assert_eq!(
+        parse("-0.00000125").checked_div_rounded(parse("0.0000001"), 0),
+        Some(parse("-13"))
+    );
+
+    let json = serde_json::to_string(&parse("1209.37")).unwrap();
+    assert_eq!(json, r#""1209.37""#);
+    assert_eq!(
+        serde_json::from_str::<Decimal>(&json).unwrap(),
+        parse("1209.37")
+    );
+    assert_eq!(
+        serde_json::from_str::<Decimal>("1210.0").unwrap(),
+        parse("1210")
+    );
+    assert_eq!(
+        serde_json::from_str::<Decimal>("-30").unwrap(),
+        parse("-30")
+    );
+}
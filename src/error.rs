@@ -3,21 +3,47 @@ use std::{
     io,
 };
 
+use binance::errors::{Error as BinanceError, ErrorKind as BinanceErrorKind};
+
+use crate::command::ParseError;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 pub struct Error {
     err: ErrorKind,
+    /// What was being done when the error happened, e.g. the symbol being read.
+    context: Option<String>,
 }
 
 enum ErrorKind {
     Serde(serde_json::Error),
+    Toml(toml::de::Error),
+    Config(String),
     PathIO(io::Error),
+    /// The exchange rejected the request or sent an unexpected response.
+    Exchange {
+        code: Option<i16>,
+        message: String,
+    },
+    /// The exchange or the daemon couldn't be reached or the connection was lost.
+    Network(String),
+    InvalidSymbol(String),
+    InvalidCurrency(String),
+    /// The command couldn't be parsed or executed.
+    Command(String),
+    /// Invalid command line arguments.
+    Usage(String),
+    /// Error of a request which the daemon sent back.
+    Remote(String),
 }
 
 impl std::error::Error for Error {}
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(context) = &self.context {
+            write!(f, "{context}: ")?;
+        }
         Display::fmt(&self.err, f)
     }
 }
@@ -26,20 +52,106 @@ impl Display for ErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ErrorKind::Serde(serde) => write!(f, "IO Serde Error: {}", serde),
+            ErrorKind::Toml(toml) => write!(f, "Config Error: {}", toml),
+            ErrorKind::Config(message) => write!(f, "Config Error: {}", message),
             ErrorKind::PathIO(io) => write!(f, "Path IO Error: {}", io),
+            ErrorKind::Exchange {
+                code: Some(code),
+                message,
+            } => write!(f, "Exchange Error: {} (code {})", message, code),
+            ErrorKind::Exchange {
+                code: None,
+                message,
+            } => write!(f, "Exchange Error: {}", message),
+            ErrorKind::Network(message) => write!(f, "Network Error: {}", message),
+            ErrorKind::InvalidSymbol(symbol) => write!(f, "Invalid Symbol: '{}'", symbol),
+            ErrorKind::InvalidCurrency(currency) => write!(f, "Invalid Currency: '{}'", currency),
+            ErrorKind::Command(message) => write!(f, "Command Error: {}", message),
+            ErrorKind::Usage(message) => write!(f, "Usage Error: {}", message),
+            ErrorKind::Remote(message) => write!(f, "{}", message),
         }
     }
 }
 
 impl Debug for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Crypto Alertabot Error: {}", self.err.to_string())
+        write!(f, "Crypto Alertabot Error: {}", self)
+    }
+}
+
+impl Error {
+    /// Binance error code of unknown symbols.
+    const INVALID_SYMBOL_CODE: i16 = -1121;
+
+    /// Invalid configuration which isn't a syntax error.
+    pub fn config<S: Into<String>>(message: S) -> Self {
+        ErrorKind::Config(message.into()).into()
+    }
+
+    pub fn invalid_symbol<S: Into<String>>(symbol: S) -> Self {
+        ErrorKind::InvalidSymbol(symbol.into()).into()
+    }
+
+    pub fn invalid_currency<S: Into<String>>(currency: S) -> Self {
+        ErrorKind::InvalidCurrency(currency.into()).into()
+    }
+
+    /// A valid command which failed, e.g. because the trigger doesn't exist.
+    pub fn command<S: Into<String>>(message: S) -> Self {
+        ErrorKind::Command(message.into()).into()
+    }
+
+    /// Invalid command line arguments.
+    pub fn usage<S: Into<String>>(message: S) -> Self {
+        ErrorKind::Usage(message.into()).into()
+    }
+
+    pub fn network<S: Into<String>>(message: S) -> Self {
+        ErrorKind::Network(message.into()).into()
+    }
+
+    /// Error sent back by the daemon which already contains its kind.
+    pub fn remote<S: Into<String>>(message: S) -> Self {
+        ErrorKind::Remote(message.into()).into()
+    }
+
+    /// Converts an error of a request about the `symbol`, recognizing unknown symbols.
+    pub fn exchange(err: BinanceError, symbol: &str) -> Self {
+        let error = Error::from(err);
+        match error.err {
+            ErrorKind::Exchange {
+                code: Some(Self::INVALID_SYMBOL_CODE),
+                ..
+            } => Error::invalid_symbol(symbol),
+            _ => error.with_context(symbol),
+        }
+    }
+
+    /// Adds what was being done when the error happened.
+    pub fn with_context<S: Into<String>>(mut self, context: S) -> Self {
+        self.context = Some(context.into());
+        self
+    }
+
+    /// Short name of the error kind used as a title.
+    pub fn title(&self) -> &'static str {
+        match self.err {
+            ErrorKind::Serde(_) | ErrorKind::PathIO(_) => "IO Error",
+            ErrorKind::Toml(_) | ErrorKind::Config(_) => "Config Error",
+            ErrorKind::Exchange { .. } => "Exchange Error",
+            ErrorKind::Network(_) => "Network Error",
+            ErrorKind::InvalidSymbol(_) => "Invalid Symbol",
+            ErrorKind::InvalidCurrency(_) => "Invalid Currency",
+            ErrorKind::Command(_) => "Command Error",
+            ErrorKind::Usage(_) => "Usage Error",
+            ErrorKind::Remote(_) => "Daemon Error",
+        }
     }
 }
 
 impl From<ErrorKind> for Error {
     fn from(err: ErrorKind) -> Self {
-        Self { err }
+        Self { err, context: None }
     }
 }
 
@@ -49,8 +161,64 @@ impl From<serde_json::error::Error> for Error {
     }
 }
 
+impl From<toml::de::Error> for Error {
+    fn from(toml: toml::de::Error) -> Self {
+        ErrorKind::Toml(toml).into()
+    }
+}
+
+impl From<toml::ser::Error> for Error {
+    fn from(toml: toml::ser::Error) -> Self {
+        ErrorKind::Config(toml.to_string()).into()
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
         ErrorKind::PathIO(err).into()
     }
 }
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        ErrorKind::Command(err.to_string()).into()
+    }
+}
+
+impl From<BinanceError> for Error {
+    fn from(err: BinanceError) -> Self {
+        let kind = match err.0 {
+            BinanceErrorKind::BinanceError(response) => ErrorKind::Exchange {
+                code: Some(response.code),
+                message: response.msg,
+            },
+            // Messages are only used for failed WebSocket connections
+            BinanceErrorKind::ReqError(_)
+            | BinanceErrorKind::IoError(_)
+            | BinanceErrorKind::Tungstenite(_)
+            | BinanceErrorKind::Msg(_) => ErrorKind::Network(err.0.to_string()),
+            kind => ErrorKind::Exchange {
+                code: None,
+                message: kind.to_string(),
+            },
+        };
+        kind.into()
+    }
+}
+
+#[test]
+fn exchange_errors() {
+    use binance::errors::BinanceContentError;
+
+    let invalid = BinanceError::from(BinanceErrorKind::BinanceError(BinanceContentError {
+        code: -1121,
+        msg: "Invalid symbol.".to_owned(),
+    }));
+    let error = Error::exchange(invalid, "ETHBTCX");
+    assert_eq!(error.title(), "Invalid Symbol");
+    assert_eq!(error.to_string(), "Invalid Symbol: 'ETHBTCX'");
+
+    let disconnected = BinanceError::from("Disconnected");
+    let error = Error::exchange(disconnected, "ETHUSDT");
+    assert_eq!(error.to_string(), "ETHUSDT: Network Error: Disconnected");
+}
@@ -0,0 +1,269 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::mpsc::{channel, Receiver},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use binance::{
+    api::{Binance, Spot, API},
+    general::General,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+const CACHE: &str = "exchange_info.json";
+/// Exchange info older than this is fetched again.
+const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Trading rules of a symbol listed on Binance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymbolInfo {
+    pub symbol: String,
+    /// Trading status like `TRADING` or `BREAK`.
+    pub status: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    /// Smallest step of the price, e.g. `0.01`.
+    pub tick_size: Option<String>,
+    /// Smallest step of the quantity, e.g. `0.0001`.
+    pub lot_size: Option<String>,
+}
+
+impl SymbolInfo {
+    pub fn is_trading(&self) -> bool {
+        self.status == "TRADING"
+    }
+
+    /// Returns the assets of the symbol like `ETH/USDT`.
+    pub fn pair(&self) -> String {
+        format!("{}/{}", self.base_asset, self.quote_asset)
+    }
+}
+
+/// Symbols listed on Binance.
+///
+/// It is fetched at most once a day and cached in the `exchange_info.json` file
+/// next to the executable so the symbols can be checked offline.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ExchangeInfo {
+    /// When the info was fetched in seconds since the Unix epoch.
+    pub fetched_at: u64,
+    /// Symbols sorted by their names.
+    pub symbols: Vec<SymbolInfo>,
+}
+
+impl ExchangeInfo {
+    pub fn get(&self, symbol: &str) -> Option<&SymbolInfo> {
+        self.symbols
+            .binary_search_by(|info| info.symbol.as_str().cmp(symbol))
+            .ok()
+            .map(|index| &self.symbols[index])
+    }
+
+    /// Checks that the `symbol` is listed on the exchange.
+    pub fn check(&self, symbol: &str) -> Result<()> {
+        match self.get(symbol) {
+            Some(_) => Ok(()),
+            None => Err(Error::invalid_symbol(symbol)),
+        }
+    }
+
+    /// Returns the symbols containing the `query`, starting with the exact match and
+    /// the symbols starting with it. Symbols which aren't traded come last.
+    pub fn search(&self, query: &str) -> Vec<&SymbolInfo> {
+        let query = query.trim().to_uppercase();
+        let mut found = self
+            .symbols
+            .iter()
+            .filter(|info| info.symbol.contains(&query))
+            .collect::<Vec<&SymbolInfo>>();
+        // The sort is stable so the symbols stay sorted by name within each group
+        found.sort_by_key(|info| {
+            (
+                info.symbol != query,
+                !info.symbol.starts_with(&query),
+                !info.is_trading(),
+            )
+        });
+        found
+    }
+
+    pub fn is_stale(&self) -> bool {
+        unix_time().saturating_sub(self.fetched_at) >= MAX_AGE.as_secs()
+    }
+}
+
+/// Exchange info as sent by Binance with only the fields which are used.
+///
+/// The filters are kept as JSON because Binance adds new kinds of filters which
+/// would fail to parse as [`binance::model::Filters`].
+#[derive(Deserialize)]
+struct BinanceExchangeInfo {
+    symbols: Vec<BinanceSymbol>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceSymbol {
+    symbol: String,
+    status: String,
+    base_asset: String,
+    quote_asset: String,
+    #[serde(default)]
+    filters: Vec<serde_json::Value>,
+}
+
+impl From<BinanceSymbol> for SymbolInfo {
+    fn from(symbol: BinanceSymbol) -> Self {
+        let filter = |kind: &str, field: &str| {
+            symbol
+                .filters
+                .iter()
+                .find(|filter| filter["filterType"] == kind)
+                .and_then(|filter| filter[field].as_str())
+                .map(trim_step)
+        };
+        Self {
+            tick_size: filter("PRICE_FILTER", "tickSize"),
+            lot_size: filter("LOT_SIZE", "stepSize"),
+            symbol: symbol.symbol,
+            status: symbol.status,
+            base_asset: symbol.base_asset,
+            quote_asset: symbol.quote_asset,
+        }
+    }
+}
+
+/// Removes the trailing zeros Binance pads the steps with, e.g. `0.01000000` -> `0.01`.
+fn trim_step(step: &str) -> String {
+    match step.contains('.') {
+        true => step.trim_end_matches('0').trim_end_matches('.').to_owned(),
+        false => step.to_owned(),
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn cache_path() -> Result<PathBuf> {
+    Ok(std::env::current_exe()?.with_file_name(CACHE))
+}
+
+/// Loads the cached exchange info returning `None` if there is none.
+///
+/// The cache is only a copy of the exchange info so a broken cache is treated as
+/// missing and fetched again.
+pub fn load_cache() -> Option<ExchangeInfo> {
+    let data = fs::read(cache_path().ok()?).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+/// Fetches the exchange info from Binance and writes it to the cache.
+pub fn fetch() -> Result<ExchangeInfo> {
+    let general: General = Binance::new(None, None);
+    let response: BinanceExchangeInfo = general
+        .client
+        .get(API::Spot(Spot::ExchangeInfo), None)
+        .map_err(|err| Error::from(err).with_context("exchange info"))?;
+    let mut symbols = response
+        .symbols
+        .into_iter()
+        .map(SymbolInfo::from)
+        .collect::<Vec<SymbolInfo>>();
+    symbols.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+    let info = ExchangeInfo {
+        fetched_at: unix_time(),
+        symbols,
+    };
+
+    // Written like the save file so a crash can't leave half of the cache behind
+    let path = cache_path()?;
+    let temporary = path.with_extension("json.tmp");
+    fs::write(&temporary, serde_json::to_vec(&info)?)?;
+    fs::rename(temporary, path)?;
+    Ok(info)
+}
+
+/// Returns the cached exchange info, fetching it first if it is missing or stale.
+///
+/// A stale cache is still used when the exchange can't be reached.
+pub fn load() -> Result<ExchangeInfo> {
+    match load_cache() {
+        Some(info) if !info.is_stale() => Ok(info),
+        cached => fetch().or_else(|err| cached.ok_or(err)),
+    }
+}
+
+/// Fetches the exchange info in a new thread sending the result to the returned receiver.
+pub fn spawn_fetch() -> Receiver<Result<ExchangeInfo>> {
+    let (tx, rx) = channel();
+    thread::spawn(move || {
+        let _ = tx.send(fetch());
+    });
+    rx
+}
+
+/// Checks that the `symbols` are listed in the cached exchange info.
+///
+/// Every symbol passes when nothing is cached yet.
+pub fn check_cached<'a>(symbols: impl IntoIterator<Item = &'a str>) -> Result<()> {
+    match load_cache() {
+        Some(info) => symbols
+            .into_iter()
+            .try_for_each(|symbol| info.check(symbol)),
+        None => Ok(()),
+    }
+}
+
+#[test]
+fn exchange_info_symbols() {
+    let response = r#"{"timezone": "UTC", "symbols": [
+        {"symbol": "ETHUSDT", "status": "TRADING", "baseAsset": "ETH", "quoteAsset": "USDT",
+         "filters": [
+            {"filterType": "PRICE_FILTER", "minPrice": "0.01000000", "tickSize": "0.01000000"},
+            {"filterType": "LOT_SIZE", "minQty": "0.00010000", "stepSize": "0.00010000"},
+            {"filterType": "SOMETHING_NEW", "limit": 3}
+         ]},
+        {"symbol": "USDTTRY", "status": "TRADING", "baseAsset": "USDT", "quoteAsset": "TRY",
+         "filters": [{"filterType": "LOT_SIZE", "stepSize": "1.00000000"}]},
+        {"symbol": "BUSDUSDT", "status": "BREAK", "baseAsset": "BUSD", "quoteAsset": "USDT"},
+        {"symbol": "USDTDAI", "status": "TRADING", "baseAsset": "USDT", "quoteAsset": "DAI"}
+    ]}"#;
+    let response: BinanceExchangeInfo = serde_json::from_str(response).unwrap();
+    let mut symbols = response
+        .symbols
+        .into_iter()
+        .map(SymbolInfo::from)
+        .collect::<Vec<SymbolInfo>>();
+    symbols.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+    let info = ExchangeInfo {
+        fetched_at: unix_time(),
+        symbols,
+    };
+
+    let eth = info.get("ETHUSDT").unwrap();
+    assert_eq!(eth.pair(), "ETH/USDT");
+    assert_eq!(eth.tick_size.as_deref(), Some("0.01"));
+    assert_eq!(eth.lot_size.as_deref(), Some("0.0001"));
+    assert_eq!(info.get("USDTTRY").unwrap().lot_size.as_deref(), Some("1"));
+    assert!(info.check("ETHUSDT").is_ok());
+    assert!(info.check("ETHUSTD").is_err());
+    assert!(!info.is_stale());
+
+    let found = |query| {
+        info.search(query)
+            .iter()
+            .map(|info| info.symbol.as_str())
+            .collect::<Vec<&str>>()
+    };
+    assert_eq!(found("usdt"), ["USDTDAI", "USDTTRY", "ETHUSDT", "BUSDUSDT"]);
+    assert_eq!(found("ethusdt"), ["ETHUSDT"]);
+    assert!(found("DOGE").is_empty());
+}
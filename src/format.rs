@@ -0,0 +1,152 @@
+use crate::exchange::SymbolInfo;
+
+/// Values with at least this many zeros after the decimal point are written
+/// compactly, e.g. `0.00000923` as `0.0₅923`.
+const COMPACT_ZEROS: usize = 4;
+/// Significant digits shown of the values whose precision isn't known.
+const SIGNIFICANT_DIGITS: usize = 4;
+/// Decimals shown of the values whose precision isn't known, unless they need more
+/// for the [`SIGNIFICANT_DIGITS`].
+const DEFAULT_DECIMALS: usize = 8;
+/// Smaller values are shown as zero when their precision isn't known.
+const MAX_ZEROS: f64 = 16.0;
+
+/// Number of the decimals the prices or quantities of a symbol are shown with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Precision {
+    /// `None` if the precision isn't known and the trailing zeros are left out.
+    decimals: Option<usize>,
+}
+
+impl Precision {
+    pub const fn decimals(decimals: usize) -> Self {
+        Self {
+            decimals: Some(decimals),
+        }
+    }
+
+    /// Precision of a step like the tick size, e.g. `0.01` has 2 decimals.
+    pub fn from_step(step: &str) -> Self {
+        let decimals = step
+            .split_once('.')
+            .map_or(0, |(_, fraction)| fraction.trim_end_matches('0').len());
+        Self::decimals(decimals)
+    }
+
+    /// Shows at least `decimals` decimals, e.g. for a level typed more precisely than
+    /// the tick size.
+    pub fn at_least(self, decimals: usize) -> Self {
+        Self {
+            decimals: self.decimals.map(|own| own.max(decimals)),
+        }
+    }
+
+    /// Precision of the prices of the symbol given by its tick size.
+    pub fn price(info: Option<&SymbolInfo>) -> Self {
+        info.and_then(|info| info.tick_size.as_deref())
+            .map(Self::from_step)
+            .unwrap_or_default()
+    }
+
+    /// Precision of the quantities of the symbol given by its lot size.
+    pub fn quantity(info: Option<&SymbolInfo>) -> Self {
+        info.and_then(|info| info.lot_size.as_deref())
+            .map(Self::from_step)
+            .unwrap_or_default()
+    }
+
+    /// Formats the `value` with thousands separators, e.g. `1,209.37`.
+    ///
+    /// Tiny values write the number of the zeros after the decimal point as a
+    /// subscript, e.g. `0.0₅923` for `0.00000923`.
+    pub fn format(self, value: impl Into<f64>) -> String {
+        let value = value.into();
+        if !value.is_finite() {
+            return value.to_string();
+        }
+        let decimals = self.decimals.unwrap_or_else(|| {
+            // About the number of the zeros after the decimal point
+            let zeros = if value == 0.0 {
+                0
+            } else {
+                (-value.abs().log10().ceil()).clamp(0.0, MAX_ZEROS) as usize
+            };
+            DEFAULT_DECIMALS.max(zeros + SIGNIFICANT_DIGITS)
+        });
+        let text = format!("{:.*}", decimals, value.abs());
+        let (integer, fraction) = text.split_once('.').unwrap_or((&text, ""));
+        let fraction = match self.decimals {
+            Some(_) => fraction,
+            None => fraction.trim_end_matches('0'),
+        };
+
+        let zero = integer == "0" && fraction.chars().all(|c| c == '0');
+        let mut out = String::new();
+        if value < 0.0 && !zero {
+            out.push('-');
+        }
+        out += &group_thousands(integer);
+        if !fraction.is_empty() {
+            out.push('.');
+            let zeros = fraction.chars().take_while(|&c| c == '0').count();
+            if integer == "0" && zeros >= COMPACT_ZEROS && zeros < fraction.len() {
+                out.push('0');
+                out.extend(zeros.to_string().chars().map(subscript));
+                out += &fraction[zeros..];
+            } else {
+                out += fraction;
+            }
+        }
+        out
+    }
+}
+
+/// Separates every three digits of the integer part by a comma.
+fn group_thousands(integer: &str) -> String {
+    let mut out = String::with_capacity(integer.len() + integer.len() / 3);
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn subscript(digit: char) -> char {
+    const SUBSCRIPTS: [char; 10] = ['₀', '₁', '₂', '₃', '₄', '₅', '₆', '₇', '₈', '₉'];
+    digit
+        .to_digit(10)
+        .map_or(digit, |digit| SUBSCRIPTS[digit as usize])
+}
+
+#[test]
+#[allow(clippy::excessive_precision)]
+fn format_prices() {
+    let unknown = Precision::default();
+    assert_eq!(unknown.format(1209.3700000001), "1,209.37");
+    assert_eq!(unknown.format(-1234567.0), "-1,234,567");
+    assert_eq!(unknown.format(0.00000923), "0.0₅923");
+    assert_eq!(unknown.format(0.0001234567), "0.00012346");
+    assert_eq!(unknown.format(0.0), "0");
+
+    assert_eq!(unknown.format(145.2674445623536789), "145.26744456");
+
+    let tick = Precision::from_step("0.01");
+    assert_eq!(tick.format(1209.3), "1,209.30");
+    assert_eq!(tick.format(1209.3700000001), "1,209.37");
+    assert_eq!(tick.format(145.2674445623536789), "145.27");
+    assert_eq!(tick.at_least(3).format(1209.375), "1,209.375");
+    assert_eq!(tick.at_least(1).format(1209.3), "1,209.30");
+    assert_eq!(tick.format(999.999), "1,000.00");
+    assert_eq!(tick.format(-0.001), "0.00");
+    assert_eq!(Precision::from_step("1").format(20512.6), "20,513");
+    assert_eq!(
+        Precision::from_step("0.00000001").format(0.00000923),
+        "0.0₅923"
+    );
+    assert_eq!(
+        Precision::from_step("0.00000001").format(0.0001),
+        "0.00010000"
+    );
+}
@@ -0,0 +1,538 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use crate::{
+    bot::{Alert, AlertLog, Bot, PriceTrigger},
+    error::{Error, Result},
+    metrics,
+    symbol::Symbol,
+};
+
+/// Requests which aren't received in this time are dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// Clients which don't read their responses or events for this long are
+/// disconnected so they can't block the bot.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+/// Largest request which is read, the bodies are single triggers.
+const MAX_REQUEST: usize = 64 * 1024;
+/// Most connections read at once, each by its own thread. The others are answered
+/// with 503 until one of them is done.
+const MAX_CONNECTIONS: usize = 32;
+/// Host listened on when the `listen` address is only a port.
+const DEFAULT_HOST: &str = "127.0.0.1";
+/// Alerts returned by `GET /alerts` without a `limit`.
+const DEFAULT_LIMIT: usize = 50;
+
+const EVENT_STREAM_HEADER: &str = "HTTP/1.1 200 OK\r\n\
+    Content-Type: text/event-stream\r\n\
+    Cache-Control: no-cache\r\n\
+    Connection: keep-alive\r\n\r\n";
+
+/// Version of the Prometheus text format served by `GET /metrics`.
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Embedded HTTP server of the daemon, configured in the `[http]` section of the config file.
+#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    /// Address the JSON API listens on, e.g. `8080` for that port on 127.0.0.1 or
+    /// `0.0.0.0:8080` for every interface. The server only runs if it is set.
+    pub listen: Option<String>,
+}
+
+impl HttpConfig {
+    /// Returns the `listen` address with the host defaulting to [`DEFAULT_HOST`].
+    pub fn address(&self) -> Option<String> {
+        self.listen
+            .as_ref()
+            .map(|listen| match listen.parse::<u16>() {
+                Ok(port) => format!("{DEFAULT_HOST}:{port}"),
+                Err(_) => listen.clone(),
+            })
+    }
+}
+
+/// HTTP request read by a connection thread.
+#[derive(Debug)]
+struct Request {
+    method: String,
+    /// Segments of the path without the empty ones, e.g. `["triggers", "0"]`.
+    path: Vec<String>,
+    query: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn path(&self) -> Vec<&str> {
+        self.path.iter().map(String::as_str).collect()
+    }
+
+    /// Returns the query parameter called `name` parsed or `None` if it is missing.
+    fn query<T: std::str::FromStr>(&self, name: &str) -> std::result::Result<Option<T>, HttpError> {
+        self.query
+            .get(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| HttpError::bad_request(format!("invalid '{name}' '{value}'")))
+            })
+            .transpose()
+    }
+
+    fn json<T: DeserializeOwned>(&self) -> std::result::Result<T, HttpError> {
+        serde_json::from_slice(&self.body)
+            .map_err(|err| HttpError::bad_request(format!("invalid body: {err}")))
+    }
+}
+
+/// Failed request answered with its status and an `{"error": "..."}` body.
+#[derive(Debug)]
+struct HttpError(u16, String);
+
+impl HttpError {
+    fn bad_request(message: impl Display) -> Self {
+        Self(400, message.to_string())
+    }
+
+    fn not_found(message: impl Display) -> Self {
+        Self(404, message.to_string())
+    }
+}
+
+impl From<Error> for HttpError {
+    fn from(err: Error) -> Self {
+        Self::bad_request(err)
+    }
+}
+
+/// Status and body of a successful request.
+type Response = std::result::Result<(u16, Value), HttpError>;
+
+/// Serves a JSON API of the triggers, the alerts and the watched symbols over
+/// HTTP so dashboards and scripts don't need the Unix socket of the control API.
+///
+/// Requests are read by their own threads while they are answered by
+/// [`HttpServer::process`] in the thread owning the bot, like in
+/// [`crate::control::ControlServer`].
+///
+/// The API has no authentication, anyone who can connect can change the triggers.
+/// It only listens on localhost unless another host is configured.
+pub struct HttpServer {
+    incoming: Receiver<(Request, TcpStream)>,
+    /// Clients of the `GET /alerts/stream` event stream.
+    streams: Vec<TcpStream>,
+    /// Indexes of the acknowledged alerts.
+    acked: HashSet<usize>,
+    /// Number of the alerts of the bot which were already streamed.
+    published_alerts: usize,
+}
+
+impl HttpServer {
+    /// Listens on the `address`, e.g. `127.0.0.1:8080`.
+    pub fn bind(address: &str) -> Result<Self> {
+        let listener = TcpListener::bind(address)
+            .map_err(|err| Error::from(err).with_context(address.to_owned()))?;
+        let (tx, incoming) = channel();
+        thread::spawn(move || accept_requests(listener, tx));
+        Ok(Self {
+            incoming,
+            streams: Vec::new(),
+            acked: HashSet::new(),
+            published_alerts: 0,
+        })
+    }
+
+    /// Answers the requests received since this was last called.
+    pub fn process(&mut self, bot: &mut Bot) {
+        while let Ok((request, mut stream)) = self.incoming.try_recv() {
+            // Only sending fails after the timeout, the connection stays open
+            let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+            if request.method == "GET" && request.path() == ["alerts", "stream"] {
+                if stream.write_all(EVENT_STREAM_HEADER.as_bytes()).is_ok() {
+                    self.streams.push(stream);
+                }
+                continue;
+            }
+            if request.method == "GET" && request.path() == ["metrics"] {
+                let metrics = metrics::render(bot);
+                let _ = write_body(
+                    &mut stream,
+                    200,
+                    "OK",
+                    METRICS_CONTENT_TYPE,
+                    metrics.as_bytes(),
+                );
+                continue;
+            }
+            let response = self.answer(&request, bot);
+            // Nothing can be done about clients which are gone
+            let _ = write_response(&mut stream, response);
+        }
+    }
+
+    fn answer(&mut self, request: &Request, bot: &mut Bot) -> Response {
+        match (request.method.as_str(), &request.path()[..]) {
+            ("GET", ["symbols"]) => {
+                let symbols = bot.watchlist().map(|tracker| tracker.symbol());
+                Ok((
+                    200,
+                    json!({ "main": bot.symbol(), "symbols": symbols.collect::<Vec<Symbol>>() }),
+                ))
+            }
+            ("POST", ["symbols"]) => {
+                #[derive(serde::Deserialize)]
+                struct Watch {
+                    symbol: String,
+                }
+                let Watch { symbol } = request.json()?;
+                let added = bot.watch(&symbol)?;
+                Ok((if added { 201 } else { 200 }, json!({ "added": added })))
+            }
+            ("DELETE", ["symbols", symbol]) => match bot.unwatch(symbol)? {
+                true => Ok((200, json!({ "removed": true }))),
+                false => Err(HttpError::not_found(format!("'{symbol}' isn't watched"))),
+            },
+            ("GET", ["stats", symbol]) => bot
+                .snapshot()
+                .watchlist
+                .into_iter()
+                .find(|watched| watched.symbol.as_str().eq_ignore_ascii_case(symbol))
+                .map(|watched| (200, json!(watched)))
+                .ok_or_else(|| HttpError::not_found(format!("'{symbol}' isn't watched"))),
+            ("GET", ["triggers"]) => Ok((200, json!({ "triggers": bot.triggers() }))),
+            ("POST", ["triggers"]) => {
+                let trigger = trigger(request)?;
+                bot.add_trigger(trigger.clone())?;
+                let index = bot.triggers().len() - 1;
+                Ok((201, json!({ "index": index, "trigger": trigger })))
+            }
+            ("GET", ["triggers", index]) => {
+                let index = parse_index(index)?;
+                let trigger = bot.triggers().get(index).ok_or_else(|| no_trigger(index))?;
+                Ok((200, json!({ "index": index, "trigger": trigger })))
+            }
+            ("PUT", ["triggers", index]) => {
+                let index = parse_index(index)?;
+                let trigger = trigger(request)?;
+                let replaced = bot
+                    .replace_trigger(index, trigger.clone())?
+                    .ok_or_else(|| no_trigger(index))?;
+                Ok((
+                    200,
+                    json!({ "index": index, "trigger": trigger, "replaced": replaced }),
+                ))
+            }
+            ("DELETE", ["triggers", index]) => {
+                let index = parse_index(index)?;
+                let removed = bot.remove_trigger(index).ok_or_else(|| no_trigger(index))?;
+                Ok((200, json!({ "removed": removed })))
+            }
+            ("GET", ["alerts"]) => self.alerts(request, bot.alerts()),
+            ("POST", ["alerts", id, "ack"]) => {
+                let id = parse_index(id)?;
+                if bot.alerts().get(id).is_none() {
+                    return Err(HttpError::not_found(format!("no alert {id}")));
+                }
+                self.acked.insert(id);
+                Ok((200, json!({ "id": id, "acked": true })))
+            }
+            (
+                _,
+                ["symbols"]
+                | ["symbols", _]
+                | ["stats", _]
+                | ["triggers"]
+                | ["triggers", _]
+                | ["alerts"]
+                | ["alerts", "stream"]
+                | ["metrics"]
+                | ["alerts", _, "ack"],
+            ) => Err(HttpError(
+                405,
+                format!("method {} not allowed", request.method),
+            )),
+            _ => Err(HttpError::not_found("no such endpoint")),
+        }
+    }
+
+    /// Returns a page of the alerts, oldest first, selected by the `offset`, `limit`
+    /// and `unacked` query parameters.
+    fn alerts(&self, request: &Request, alerts: &AlertLog) -> Response {
+        let offset = request.query("offset")?.unwrap_or(0);
+        let limit = request.query("limit")?.unwrap_or(DEFAULT_LIMIT);
+        let unacked = request.query("unacked")?.unwrap_or(false);
+
+        let selected = alerts
+            .iter()
+            .filter(|(id, _)| !unacked || !self.acked.contains(id))
+            .collect::<Vec<(usize, &Alert)>>();
+        let page = selected
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .map(|&(id, alert)| self.alert_json(id, alert))
+            .collect::<Vec<Value>>();
+        Ok((
+            200,
+            json!({ "total": selected.len(), "offset": offset, "alerts": page }),
+        ))
+    }
+
+    fn alert_json(&self, id: usize, alert: &Alert) -> Value {
+        json!({
+            "id": id,
+            "acked": self.acked.contains(&id),
+            "message": alert.to_string(),
+            "alert": alert,
+        })
+    }
+
+    /// Sends the new alerts to the clients of the event stream.
+    pub fn publish(&mut self, bot: &Bot) {
+        let alerts = bot.alerts();
+        let events = alerts
+            .iter()
+            .filter(|&(id, _)| id >= self.published_alerts)
+            .map(|(id, alert)| {
+                format!(
+                    "id: {id}\nevent: alert\ndata: {}\n\n",
+                    self.alert_json(id, alert)
+                )
+            })
+            .collect::<String>();
+        self.published_alerts = alerts.raised();
+        // The dropped alerts can't be acknowledged anymore
+        if let Some((first, _)) = alerts.iter().next() {
+            self.acked.retain(|&id| id >= first);
+        }
+        if !events.is_empty() {
+            self.streams
+                .retain_mut(|stream| stream.write_all(events.as_bytes()).is_ok());
+        }
+    }
+
+    /// Forgets the alerts of the previous bot after it was replaced.
+    pub fn reset(&mut self) {
+        self.acked.clear();
+        self.published_alerts = 0;
+    }
+}
+
+/// Reads the trigger in the body of the `request` and checks it.
+fn trigger(request: &Request) -> std::result::Result<PriceTrigger, HttpError> {
+    let trigger: PriceTrigger = request.json()?;
+    trigger.validate().map_err(HttpError::bad_request)?;
+    Ok(trigger)
+}
+
+fn parse_index(index: &str) -> std::result::Result<usize, HttpError> {
+    index
+        .parse()
+        .map_err(|_| HttpError::bad_request(format!("invalid index '{index}'")))
+}
+
+fn no_trigger(index: usize) -> HttpError {
+    HttpError::not_found(format!("no trigger {index}"))
+}
+
+/// Accepts the connections until the server is dropped, reading each request in a new
+/// thread, at most [`MAX_CONNECTIONS`] at once.
+fn accept_requests(listener: TcpListener, tx: Sender<(Request, TcpStream)>) {
+    let connections = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+        let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+        if connections.load(Ordering::SeqCst) >= MAX_CONNECTIONS {
+            let busy = HttpError(503, "too many connections".to_owned());
+            let _ = write_response(&mut stream, Err(busy));
+            continue;
+        }
+        connections.fetch_add(1, Ordering::SeqCst);
+        let connections = Arc::clone(&connections);
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+            match read_request(&mut stream) {
+                Ok(request) => {
+                    let _ = tx.send((request, stream));
+                }
+                Err(err) => {
+                    let _ = write_response(&mut stream, Err(err));
+                }
+            }
+            connections.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+}
+
+/// Reads a request with its body given by the `Content-Length` header.
+fn read_request(stream: &mut impl Read) -> std::result::Result<Request, HttpError> {
+    let mut data = Vec::new();
+    let mut buffer = [0; 4096];
+    loop {
+        let read = stream.read(&mut buffer).map_err(HttpError::bad_request)?;
+        if read == 0 {
+            return Err(HttpError::bad_request("incomplete request"));
+        }
+        data.extend_from_slice(&buffer[..read]);
+
+        let mut headers = [httparse::EMPTY_HEADER; 32];
+        let mut parsed = httparse::Request::new(&mut headers);
+        let header_len = match parsed.parse(&data).map_err(HttpError::bad_request)? {
+            httparse::Status::Complete(len) => len,
+            httparse::Status::Partial if data.len() < MAX_REQUEST => continue,
+            httparse::Status::Partial => return Err(too_large()),
+        };
+        let content_length = parsed
+            .headers
+            .iter()
+            .find(|header| header.name.eq_ignore_ascii_case("content-length"))
+            .map(|header| {
+                std::str::from_utf8(header.value)
+                    .ok()
+                    .and_then(|value| value.trim().parse::<usize>().ok())
+                    .ok_or_else(|| HttpError::bad_request("invalid Content-Length"))
+            })
+            .transpose()?
+            .unwrap_or(0);
+        if content_length > MAX_REQUEST {
+            return Err(too_large());
+        }
+        let method = parsed.method.unwrap_or_default().to_owned();
+        let (path, query) = parse_target(parsed.path.unwrap_or_default());
+
+        let mut body = data.split_off(header_len);
+        if body.len() < content_length {
+            let mut rest = vec![0; content_length - body.len()];
+            stream
+                .read_exact(&mut rest)
+                .map_err(|_| HttpError::bad_request("incomplete body"))?;
+            body.extend(rest);
+        }
+        body.truncate(content_length);
+        return Ok(Request {
+            method,
+            path,
+            query,
+            body,
+        });
+    }
+}
+
+fn too_large() -> HttpError {
+    HttpError(413, "request too large".to_owned())
+}
+
+/// Splits the request target like `/alerts?limit=10` into the path segments and
+/// the query parameters.
+fn parse_target(target: &str) -> (Vec<String>, HashMap<String, String>) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let path = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_owned)
+        .collect();
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (name.to_owned(), value.to_owned())
+        })
+        .collect();
+    (path, query)
+}
+
+fn write_response(stream: &mut impl Write, response: Response) -> Result<()> {
+    let (status, body) =
+        response.unwrap_or_else(|HttpError(status, message)| (status, json!({ "error": message })));
+    let reason = match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        503 => "Service Unavailable",
+        _ => "",
+    };
+    write_body(
+        stream,
+        status,
+        reason,
+        "application/json",
+        &serde_json::to_vec(&body)?,
+    )
+}
+
+fn write_body(
+    stream: &mut impl Write,
+    status: u16,
+    reason: &str,
+    content_type: &str,
+    body: &[u8],
+) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status} {reason}\r\n\
+        Content-Type: {content_type}\r\n\
+        Content-Length: {}\r\n\
+        Connection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()?;
+    Ok(())
+}
+
+#[test]
+fn http_requests() {
+    let data = b"POST /triggers/?dry=1 HTTP/1.1\r\nHost: localhost\r\nContent-Length: 43\r\n\r\n\
+        {\"price\": 2000.5, \"condition\": \"higher_eq\"}\n";
+    let request = read_request(&mut &data[..]).unwrap();
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.path(), ["triggers"]);
+    assert_eq!(request.query::<u8>("dry").unwrap(), Some(1));
+    let trigger = trigger(&request).unwrap();
+    assert!(trigger.enabled && trigger.symbol.is_none());
+
+    let data = b"GET /alerts?limit=ten HTTP/1.1\r\n\r\n";
+    let request = read_request(&mut &data[..]).unwrap();
+    assert!(matches!(
+        request.query::<usize>("limit"),
+        Err(HttpError(400, _))
+    ));
+    assert!(matches!(
+        read_request(&mut &b"GET /alerts HTTP/1.1\r\n"[..]),
+        Err(HttpError(400, _))
+    ));
+
+    let mut written = Vec::new();
+    write_response(&mut written, Err(no_trigger(3))).unwrap();
+    let written = String::from_utf8(written).unwrap();
+    assert!(written.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    assert!(written.ends_with("\r\n\r\n{\"error\":\"no trigger 3\"}"));
+
+    let address = |listen: &str| {
+        let listen = Some(listen.to_owned());
+        HttpConfig { listen }.address().unwrap()
+    };
+    assert_eq!(address("8080"), "127.0.0.1:8080");
+    assert_eq!(address("0.0.0.0:8080"), "0.0.0.0:8080");
+}
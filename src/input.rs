@@ -71,6 +71,13 @@ impl InputHandler {
         }
     }
 
+    /// Inserts the `text` at the cursor position skipping any control characters.
+    pub fn insert_str(&mut self, text: &str) {
+        text.chars()
+            .filter(|c| !c.is_control())
+            .for_each(|c| self.add_char(c));
+    }
+
     fn left_key(&mut self) {
         match self.cursor {
             CursorPosition::Pos(index) => {
@@ -0,0 +1,273 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Actions which can be triggered from the Control mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    FocusNext,
+    FocusPrevious,
+    ScrollUp,
+    ScrollDown,
+    ToggleMaximized,
+    CycleWatchlistSort,
+    NewTrigger,
+    RemoveTrigger,
+    PickSymbol,
+    EnterEditing,
+    Help,
+    Quit,
+}
+
+impl Action {
+    /// All actions in the order they are listed in the help overlay.
+    pub const ALL: &[Action] = &[
+        Action::FocusNext,
+        Action::FocusPrevious,
+        Action::ScrollUp,
+        Action::ScrollDown,
+        Action::ToggleMaximized,
+        Action::CycleWatchlistSort,
+        Action::NewTrigger,
+        Action::RemoveTrigger,
+        Action::PickSymbol,
+        Action::EnterEditing,
+        Action::Help,
+        Action::Quit,
+    ];
+
+    /// Name of the action in the config file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::FocusNext => "focus_next",
+            Action::FocusPrevious => "focus_previous",
+            Action::ScrollUp => "scroll_up",
+            Action::ScrollDown => "scroll_down",
+            Action::ToggleMaximized => "toggle_maximized",
+            Action::CycleWatchlistSort => "cycle_watchlist_sort",
+            Action::NewTrigger => "new_trigger",
+            Action::RemoveTrigger => "remove_trigger",
+            Action::PickSymbol => "pick_symbol",
+            Action::EnterEditing => "enter_editing",
+            Action::Help => "help",
+            Action::Quit => "quit",
+        }
+    }
+
+    fn default_bindings(self) -> Vec<KeyBinding> {
+        let keys: &[KeyCode] = match self {
+            Action::FocusNext => &[KeyCode::Tab],
+            Action::FocusPrevious => &[KeyCode::BackTab],
+            Action::ScrollUp => &[KeyCode::Char('k'), KeyCode::Up],
+            Action::ScrollDown => &[KeyCode::Char('j'), KeyCode::Down],
+            Action::ToggleMaximized => &[KeyCode::Char('m')],
+            Action::CycleWatchlistSort => &[KeyCode::Char('s')],
+            Action::NewTrigger => &[KeyCode::Char('n')],
+            Action::RemoveTrigger => &[KeyCode::Char('d')],
+            Action::PickSymbol => &[KeyCode::Char('w')],
+            Action::EnterEditing => &[KeyCode::Char('i')],
+            Action::Help => &[KeyCode::Char('?')],
+            Action::Quit => &[KeyCode::Char('q')],
+        };
+        keys.iter().map(|&code| KeyBinding::new(code)).collect()
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::FocusNext => "Focus the next panel",
+            Action::FocusPrevious => "Focus the previous panel",
+            Action::ScrollUp => "Scroll the focused panel up",
+            Action::ScrollDown => "Scroll the focused panel down",
+            Action::ToggleMaximized => "Maximize the focused panel or restore the layout",
+            Action::CycleWatchlistSort => "Change the watchlist sort order",
+            Action::NewTrigger => "Open the new trigger form",
+            Action::RemoveTrigger => "Remove the selected trigger",
+            Action::PickSymbol => "Pick a symbol to watch",
+            Action::EnterEditing => "Enter the Editing mode",
+            Action::Help => "Toggle this help",
+            Action::Quit => "Quit",
+        }
+    }
+}
+
+/// Maps the key presses to the Control mode [`Action`]s.
+#[derive(Debug)]
+pub struct Keymap {
+    bindings: HashMap<KeyBinding, Action>,
+}
+
+impl Keymap {
+    /// Creates the default keymap where actions found in `overrides` get their
+    /// default bindings replaced.
+    pub fn new(overrides: &HashMap<Action, Vec<KeyBinding>>) -> Self {
+        let mut bindings = HashMap::new();
+        for &action in Action::ALL {
+            let keys = match overrides.get(&action) {
+                Some(keys) => keys.clone(),
+                None => action.default_bindings(),
+            };
+            for key in keys {
+                bindings.insert(key, action);
+            }
+        }
+        Self { bindings }
+    }
+
+    pub fn action(&self, event: KeyEvent) -> Option<Action> {
+        self.bindings.get(&KeyBinding::from(event)).copied()
+    }
+
+    /// Returns the bindings of every action in a human readable form.
+    pub fn help(&self) -> Vec<(String, &'static str)> {
+        Action::ALL
+            .iter()
+            .map(|&action| {
+                let mut keys = self
+                    .bindings
+                    .iter()
+                    .filter(|(_, a)| **a == action)
+                    .map(|(key, _)| key.to_string())
+                    .collect::<Vec<String>>();
+                keys.sort();
+                (keys.join(", "), action.description())
+            })
+            .collect()
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&HashMap::new())
+    }
+}
+
+/// A single key combination written as for example `q`, `Tab` or `Ctrl-c`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    fn new(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+}
+
+impl From<KeyEvent> for KeyBinding {
+    fn from(event: KeyEvent) -> Self {
+        // Shift is already a part of upper case chars and the BackTab key
+        let modifiers = match event.code {
+            KeyCode::Char(_) | KeyCode::BackTab => event.modifiers - KeyModifiers::SHIFT,
+            _ => event.modifiers,
+        };
+        Self {
+            code: event.code,
+            modifiers,
+        }
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut key = s;
+        // A single '-' is a valid key so modifiers must be followed by something
+        while let Some((modifier, rest)) = key.split_once('-').filter(|(_, r)| !r.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                _ => return Err(format!("unknown key modifier '{modifier}' in '{s}'")),
+            };
+            key = rest;
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_lowercase().as_str() {
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                _ => return Err(format!("unknown key '{key}'")),
+            },
+        };
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::PageUp => write!(f, "PageUp"),
+            KeyCode::PageDown => write!(f, "PageDown"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+// Parsed from the name because TOML gives the table keys as plain strings
+impl<'de> Deserialize<'de> for Action {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Action::ALL
+            .iter()
+            .copied()
+            .find(|action| action.name() == name)
+            .ok_or_else(|| de::Error::custom(format!("unknown action '{name}'")))
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyBinding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for KeyBinding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[test]
+fn parse_key_bindings() {
+    let ctrl_c = "Ctrl-c".parse::<KeyBinding>().unwrap();
+    assert_eq!(ctrl_c.code, KeyCode::Char('c'));
+    assert_eq!(ctrl_c.modifiers, KeyModifiers::CONTROL);
+    assert_eq!("-".parse::<KeyBinding>().unwrap().code, KeyCode::Char('-'));
+    assert_eq!("Tab".parse::<KeyBinding>().unwrap().to_string(), "Tab");
+    assert!("Hyper-x".parse::<KeyBinding>().is_err());
+
+    let keymap = Keymap::default();
+    let event = KeyEvent::new(KeyCode::Char('?'), KeyModifiers::SHIFT);
+    assert_eq!(keymap.action(event), Some(Action::Help));
+}
@@ -0,0 +1,334 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use tui::layout::{Constraint, Direction, Layout, Rect};
+
+use crate::tui::Panel;
+
+/// Terminals narrower than this stack all panels vertically.
+const NARROW_WIDTH: u16 = 60;
+/// Terminals shorter than this show only the focused panel.
+const SHORT_HEIGHT: u16 = 16;
+
+/// Arrangement of the TUI panels, configured in the `[layout]` section of the config file.
+///
+/// Every node is either a panel or a split containing more nodes:
+///
+/// ```toml
+/// [layout]
+/// direction = "horizontal"
+/// margin = 1
+/// children = [
+///     { panel = "alerts", size = "70%" },
+///     { direction = "vertical", children = [
+///         { panel = "live_stats", size = "3" },
+///         { panel = "triggers" },
+///         { panel = "input", size = "min:5" },
+///     ] },
+/// ]
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum LayoutNode {
+    Panel {
+        panel: Panel,
+        #[serde(default)]
+        size: Size,
+    },
+    Split {
+        direction: SplitDirection,
+        #[serde(default)]
+        size: Size,
+        #[serde(default)]
+        margin: u16,
+        children: Vec<LayoutNode>,
+    },
+}
+
+impl LayoutNode {
+    /// Checks that the layout contains the Input Box since it can't be hidden.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.contains(Panel::InputBox) {
+            Ok(())
+        } else {
+            Err(format!(
+                "the layout must contain the '{}' panel",
+                Panel::InputBox
+            ))
+        }
+    }
+
+    pub fn contains(&self, panel: Panel) -> bool {
+        match self {
+            LayoutNode::Panel { panel: p, .. } => *p == panel,
+            LayoutNode::Split { children, .. } => children.iter().any(|c| c.contains(panel)),
+        }
+    }
+
+    fn size(&self) -> Size {
+        match self {
+            LayoutNode::Panel { size, .. } | LayoutNode::Split { size, .. } => *size,
+        }
+    }
+
+    /// Splits the `area` between the visible panels.
+    ///
+    /// Very narrow terminals get the panels stacked vertically while very short
+    /// terminals only show the `focused` panel.
+    pub fn split(
+        &self,
+        area: Rect,
+        visible: &dyn Fn(Panel) -> bool,
+        focused: Panel,
+    ) -> Vec<(Panel, Rect)> {
+        if area.height < SHORT_HEIGHT {
+            return vec![(focused, area)];
+        }
+
+        let mut areas = Vec::new();
+        if area.width < NARROW_WIDTH {
+            let panels = Panel::ORDER
+                .iter()
+                .copied()
+                .filter(|&p| visible(p) && self.contains(p))
+                .collect::<Vec<Panel>>();
+            let constraints = panels
+                .iter()
+                .map(|panel| match panel {
+                    Panel::LivePrice => Constraint::Length(3),
+                    Panel::InputBox => Constraint::Length(5),
+                    Panel::AlertBox
+                    | Panel::TriggerList
+                    | Panel::Watchlist
+                    | Panel::Depth
+                    | Panel::Trades
+                    | Panel::Log => Constraint::Min(3),
+                })
+                .collect::<Vec<Constraint>>();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(constraints)
+                .split(area);
+            areas.extend(panels.into_iter().zip(chunks));
+        } else {
+            self.split_node(area, visible, &mut areas);
+        }
+        areas
+    }
+
+    fn split_node(
+        &self,
+        area: Rect,
+        visible: &dyn Fn(Panel) -> bool,
+        areas: &mut Vec<(Panel, Rect)>,
+    ) {
+        match self {
+            LayoutNode::Panel { panel, .. } => areas.push((*panel, area)),
+            LayoutNode::Split {
+                direction,
+                margin,
+                children,
+                ..
+            } => {
+                // Splits without any visible panels are skipped entirely
+                let children = children
+                    .iter()
+                    .filter(|c| c.has_visible(visible))
+                    .collect::<Vec<&LayoutNode>>();
+                let chunks = Layout::default()
+                    .direction(direction.into())
+                    .margin(*margin)
+                    .constraints(
+                        children
+                            .iter()
+                            .map(|c| c.size().into())
+                            .collect::<Vec<Constraint>>(),
+                    )
+                    .split(area);
+                for (child, chunk) in children.into_iter().zip(chunks) {
+                    child.split_node(chunk, visible, areas);
+                }
+            }
+        }
+    }
+
+    fn has_visible(&self, visible: &dyn Fn(Panel) -> bool) -> bool {
+        match self {
+            LayoutNode::Panel { panel, .. } => visible(*panel),
+            LayoutNode::Split { children, .. } => children.iter().any(|c| c.has_visible(visible)),
+        }
+    }
+}
+
+impl Default for LayoutNode {
+    /// Alerts, the watchlist and trades on the left, the order book, triggers, the Input Box
+    /// and the log on the right and Live Stats at the bottom.
+    fn default() -> Self {
+        let panel = |panel, size| LayoutNode::Panel { panel, size };
+        LayoutNode::Split {
+            direction: SplitDirection::Vertical,
+            size: Size::Fill,
+            margin: 1,
+            children: vec![
+                LayoutNode::Split {
+                    direction: SplitDirection::Horizontal,
+                    size: Size::Min(6),
+                    margin: 0,
+                    children: vec![
+                        LayoutNode::Split {
+                            direction: SplitDirection::Vertical,
+                            size: Size::Percentage(60),
+                            margin: 0,
+                            children: vec![
+                                panel(Panel::AlertBox, Size::Percentage(40)),
+                                panel(Panel::Watchlist, Size::Percentage(30)),
+                                panel(Panel::Trades, Size::Percentage(30)),
+                            ],
+                        },
+                        LayoutNode::Split {
+                            direction: SplitDirection::Vertical,
+                            size: Size::Percentage(40),
+                            margin: 0,
+                            children: vec![
+                                panel(Panel::Depth, Size::Percentage(35)),
+                                panel(Panel::TriggerList, Size::Percentage(25)),
+                                panel(Panel::InputBox, Size::Percentage(20)),
+                                panel(Panel::Log, Size::Percentage(20)),
+                            ],
+                        },
+                    ],
+                },
+                panel(Panel::LivePrice, Size::Length(3)),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitDirection {
+    Horizontal,
+    Vertical,
+}
+
+impl From<&SplitDirection> for Direction {
+    fn from(direction: &SplitDirection) -> Self {
+        match direction {
+            SplitDirection::Horizontal => Direction::Horizontal,
+            SplitDirection::Vertical => Direction::Vertical,
+        }
+    }
+}
+
+/// Size of a layout node written as `60%`, `3` (cells), `min:6`, `max:10` or `fill`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Size {
+    Percentage(u16),
+    Length(u16),
+    Min(u16),
+    Max(u16),
+    #[default]
+    Fill,
+}
+
+impl From<Size> for Constraint {
+    fn from(size: Size) -> Self {
+        match size {
+            Size::Percentage(p) => Constraint::Percentage(p),
+            Size::Length(l) => Constraint::Length(l),
+            Size::Min(m) => Constraint::Min(m),
+            Size::Max(m) => Constraint::Max(m),
+            Size::Fill => Constraint::Min(0),
+        }
+    }
+}
+
+impl FromStr for Size {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid size '{s}'");
+        let number = |n: &str| n.trim().parse::<u16>().map_err(|_| invalid());
+
+        if s == "fill" {
+            Ok(Size::Fill)
+        } else if let Some(percentage) = s.strip_suffix('%') {
+            let percentage = number(percentage)?;
+            if percentage > 100 {
+                return Err(invalid());
+            }
+            Ok(Size::Percentage(percentage))
+        } else if let Some(min) = s.strip_prefix("min:") {
+            Ok(Size::Min(number(min)?))
+        } else if let Some(max) = s.strip_prefix("max:") {
+            Ok(Size::Max(number(max)?))
+        } else {
+            Ok(Size::Length(number(s)?))
+        }
+    }
+}
+
+impl Display for Size {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Size::Percentage(p) => write!(f, "{p}%"),
+            Size::Length(l) => write!(f, "{l}"),
+            Size::Min(m) => write!(f, "min:{m}"),
+            Size::Max(m) => write!(f, "max:{m}"),
+            Size::Fill => write!(f, "fill"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Size {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Size {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[test]
+fn parse_layout() {
+    #[derive(Deserialize)]
+    struct Wrapper {
+        layout: LayoutNode,
+    }
+    let wrapper: Wrapper = toml::from_str(
+        r#"
+        [layout]
+        direction = "horizontal"
+        children = [
+            { panel = "alerts", size = "70%" },
+            { direction = "vertical", children = [
+                { panel = "live_stats", size = "3" },
+                { panel = "input", size = "min:5" },
+            ] },
+        ]
+        "#,
+    )
+    .unwrap();
+    assert!(wrapper.layout.validate().is_ok());
+
+    let area = Rect::new(0, 0, 100, 30);
+    let areas = wrapper
+        .layout
+        .split(area, &|p| p != Panel::LivePrice, Panel::InputBox);
+    let panels = areas.iter().map(|(p, _)| *p).collect::<Vec<Panel>>();
+    assert_eq!(panels, [Panel::AlertBox, Panel::InputBox]);
+    assert_eq!(areas[0].1.width, 70);
+    assert_eq!(areas[1].1.height, 30);
+
+    // Short terminals only show the focused panel
+    let short = Rect::new(0, 0, 100, 10);
+    let areas = wrapper.layout.split(short, &|_| true, Panel::AlertBox);
+    assert_eq!(areas, [(Panel::AlertBox, short)]);
+
+    assert_eq!("min:6".parse(), Ok(Size::Min(6)));
+    assert!("120%".parse::<Size>().is_err());
+}
@@ -3,52 +3,160 @@ use std::{
     time::{Duration, Instant},
 };
 
-use ::tui::{backend::CrosstermBackend, Terminal};
-use bot::Bot;
-use console::Console;
-use crossterm::{
-    event::{self, Event},
-    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+use ::tui::{
+    backend::{Backend, CrosstermBackend},
+    Terminal,
 };
+use bot::BotControl;
+use cli::{Cli, CliCommand};
+use config::Config;
+use console::Console;
+use crossterm::event::{self, Event};
 use error::Result;
+use keymap::Keymap;
+use signals::ShutdownSignal;
+use sinks::{AlertSinks, SinkConfig};
+use terminal::TerminalGuard;
+use theme::Theme;
 
 mod bot;
+mod cli;
+mod command;
+mod config;
+mod connection;
 mod console;
+#[cfg(unix)]
+mod control;
+mod crash;
+mod currency;
+mod daemon;
+mod decimal;
 mod error;
+mod exchange;
+mod format;
+mod http;
 mod input;
+mod keymap;
+mod layout;
+mod metrics;
+mod popup;
+mod save;
+mod signals;
+mod sinks;
+mod symbol;
+mod terminal;
+mod theme;
 mod tui;
-//mod save;
 
+/// Default interval between the reads of the market data, see [`config::Config::poll_interval`].
 const TICK_INTERVAL: Duration = Duration::from_millis(1000);
 const RESIZE_BATCH_WAIT_DURATION: Duration = Duration::from_millis(100);
+/// Changes of the saved state are written at most this often.
+const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+/// How long the shutdown waits for the reader threads to exit.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
 
 fn main() -> Result<()> {
+    let cli = Cli::parse(std::env::args().skip(1))?;
+    if let CliCommand::Help = cli.command {
+        print!("{}", cli::usage());
+        return Ok(());
+    }
+
+    let (config, config_path) = config::load_config(cli.config.as_deref(), &cli.overrides)?;
+    match cli.command {
+        CliCommand::ConfigCheck => config::check(&config, config_path.as_deref()),
+        CliCommand::Tui => run_tui(config, false),
+        CliCommand::Attach => run_tui(config, true),
+        CliCommand::Daemon => daemon::run(config, &cli),
+        command => {
+            config.validate()?;
+            cli::run(command, &config)
+        }
+    }
+}
+
+/// Runs the TUI with its own bot or, if `attach`, with the bot of the running daemon.
+fn run_tui(config: Config, attach: bool) -> Result<()> {
+    config.validate()?;
+    let theme = match &config.theme {
+        Some(name) => Theme::load(name)?,
+        None => Theme::default(),
+    };
+    let sinks = match attach {
+        // The daemon delivers the alerts to the other sinks itself
+        true => AlertSinks::new(&SinkConfig {
+            bell: config.sinks.bell,
+            ..SinkConfig::default()
+        })?,
+        false => AlertSinks::new(&config.sinks)?,
+    };
+
     // =================== LOAD SAVED DATA ===================
-    /*let save_data = match save::load_create_save_file() {
-        Ok(data) => data,
-        Err(err) => panic!("Alertabot Error: {}", err),
-    };*/
+    let mut bot: Box<dyn BotControl> = match attach {
+        true => attach_to_daemon(&config)?,
+        false => Box::new(config.create_bot(save::load_save_file()?)?),
+    };
 
     // ======================== SETUP ========================
-    let mut stdout = io::stdout();
-    crossterm::execute!(stdout, EnterAlternateScreen)?;
-    terminal::enable_raw_mode()?;
-    let backend = CrosstermBackend::new(stdout);
+    crash::install_panic_hook();
+    let shutdown_signal = ShutdownSignal::register()?;
+    let _terminal_guard = TerminalGuard::enter()?;
+    let backend = CrosstermBackend::new(io::stdout());
     let terminal = Terminal::new(backend)?;
-    let mut console = Console::new(terminal)?;
-    let mut bot = Bot::new()?;
+    let mut console = Console::new(
+        terminal,
+        Keymap::new(&config.keymap),
+        theme,
+        config.layout.clone().unwrap_or_default(),
+        sinks,
+    )?;
+    // The daemon already delivered its earlier alerts
+    if attach {
+        console.skip_alerts(&bot.snapshot());
+    }
+    let poll_interval = config.poll_interval();
 
     // ====================== MAIN LOOP ======================
+    let result = run(&mut console, bot.as_mut(), poll_interval, &shutdown_signal);
+
+    // ===================== SAVE && EXIT =====================
+    // The bot is shut down even if the main loop failed
+    let shutdown_result = shutdown(&mut console, bot.as_mut());
+    result.and(shutdown_result)
+}
 
+#[cfg(unix)]
+fn attach_to_daemon(config: &Config) -> Result<Box<dyn BotControl>> {
+    let socket = config.daemon.socket()?;
+    Ok(Box::new(control::ControlClient::connect(&socket)?))
+}
+
+#[cfg(not(unix))]
+fn attach_to_daemon(_config: &Config) -> Result<Box<dyn BotControl>> {
+    Err(error::Error::usage(
+        "attaching to the daemon needs Unix sockets",
+    ))
+}
+
+fn run<B: Backend>(
+    console: &mut Console<B>,
+    bot: &mut dyn BotControl,
+    tick_interval: Duration,
+    shutdown_signal: &ShutdownSignal,
+) -> Result<()> {
     let mut last = Instant::now();
+    let mut last_save = Instant::now();
     loop {
         console.render()?;
 
         let elapsed = last.elapsed();
-        let timeout = TICK_INTERVAL.checked_sub(elapsed).unwrap_or(Duration::ZERO);
-        if event::poll(timeout)? == true {
+        let timeout = tick_interval.checked_sub(elapsed).unwrap_or(Duration::ZERO);
+        if event::poll(timeout)? {
             match event::read()? {
-                event::Event::Key(key) => console.process_input(key),
+                event::Event::Key(key) => console.process_input(key, bot),
+                event::Event::Paste(text) => console.process_paste(text, bot),
+                event::Event::Mouse(mouse) => console.process_mouse(mouse),
                 event::Event::Resize(..) => {
                     process_resize_batch()?;
                     console.resize()?;
@@ -58,23 +166,44 @@ fn main() -> Result<()> {
         }
 
         // One tick happens every 1 second. 1 tick == 1 second
-        if elapsed >= TICK_INTERVAL {
+        if elapsed >= tick_interval {
             last = Instant::now();
 
             bot.update();
-            console.update(&bot);
+            console.update(bot);
+            if let Some(data) = bot.save_data() {
+                save::schedule(&data)?;
+            }
+        }
+
+        if last_save.elapsed() >= SAVE_INTERVAL {
+            last_save = Instant::now();
+            save::flush()?;
         }
 
+        if shutdown_signal.received() {
+            console.request_exit("received a shutdown signal");
+        }
         if console.should_exit() {
-            break;
+            return Ok(());
         }
     }
+}
 
-    // ===================== SAVE && EXIT =====================
-    terminal::disable_raw_mode()?;
-    crossterm::execute!(io::stdout(), LeaveAlternateScreen)?;
-
-    Ok(())
+/// Stops the reader threads, delivers the remaining alerts and saves the state.
+///
+/// Every way of exiting ends here: the `exit` command, the quit key, Ctrl-C and
+/// the shutdown signals.
+fn shutdown<B: Backend>(console: &mut Console<B>, bot: &mut dyn BotControl) -> Result<()> {
+    // Shows why the bot is exiting while waiting for the threads
+    let render_result = console.render();
+    bot.shutdown(SHUTDOWN_TIMEOUT);
+    console.deliver_alerts(&bot.snapshot());
+    if let Some(data) = bot.save_data() {
+        save::schedule(&data)?;
+    }
+    save::flush()?;
+    render_result
 }
 
 /// When the user resizes the terminal, resize events come in batches meaning
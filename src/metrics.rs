@@ -0,0 +1,221 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use crate::{bot::Bot, symbol::Symbol};
+
+/// Upper bounds in seconds of the buckets of the request latency histograms.
+const LATENCY_BUCKETS: [f64; 9] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Counters and histograms of the process exported in the Prometheus text format.
+///
+/// They are global so the reader threads can record their requests and so they
+/// keep counting when the daemon replaces its bot on reload.
+static METRICS: Mutex<Metrics> = Mutex::new(Metrics::new());
+
+struct Metrics {
+    /// Number of the alerts keyed by the target of their triggers or `stale`.
+    alerts: BTreeMap<&'static str, u64>,
+    /// Number of the failed reads keyed by the symbol and what was read.
+    fetch_errors: BTreeMap<(Symbol, &'static str), u64>,
+    /// Latency of the `GET /api/v3/ticker/24hr` requests.
+    price_stats_latency: Histogram,
+}
+
+struct Histogram {
+    /// Number of the observations in each of the [`LATENCY_BUCKETS`], not cumulative.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    const fn new() -> Self {
+        Self {
+            buckets: [0; LATENCY_BUCKETS.len()],
+            count: 0,
+            sum: 0.0,
+        }
+    }
+
+    fn observe(&mut self, seconds: f64) {
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|&le| seconds <= le) {
+            self.buckets[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        header(out, name, help, "histogram");
+        let mut cumulative = 0;
+        for (le, count) in LATENCY_BUCKETS.iter().zip(self.buckets) {
+            cumulative += count;
+            let _ = writeln!(out, "{name}_bucket{{le=\"{le}\"}} {cumulative}");
+        }
+        let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {}", self.count);
+        let _ = writeln!(out, "{name}_sum {}", self.sum);
+        let _ = writeln!(out, "{name}_count {}", self.count);
+    }
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Self {
+            alerts: BTreeMap::new(),
+            fetch_errors: BTreeMap::new(),
+            price_stats_latency: Histogram::new(),
+        }
+    }
+
+    fn render(&self, out: &mut String) {
+        header(
+            out,
+            "alertabot_alerts_total",
+            "Alerts fired by the triggers of each target.",
+            "counter",
+        );
+        for (target, count) in &self.alerts {
+            let _ = writeln!(out, "alertabot_alerts_total{{target=\"{target}\"}} {count}");
+        }
+        header(
+            out,
+            "alertabot_fetch_errors_total",
+            "Failed reads of the market data.",
+            "counter",
+        );
+        for ((symbol, source), count) in &self.fetch_errors {
+            let _ = writeln!(
+                out,
+                "alertabot_fetch_errors_total{{symbol=\"{}\",source=\"{source}\"}} {count}",
+                escape(symbol.as_str())
+            );
+        }
+        self.price_stats_latency.render(
+            out,
+            "alertabot_price_stats_request_duration_seconds",
+            "Latency of the 24h price stats requests.",
+        );
+    }
+}
+
+fn metrics() -> MutexGuard<'static, Metrics> {
+    // The counters stay usable even if a thread panicked while holding them
+    METRICS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Counts an alert fired by a trigger of the `target`, e.g. `price`, or `stale` for
+/// the stale price alerts.
+pub fn count_alert(target: &'static str) {
+    *metrics().alerts.entry(target).or_default() += 1;
+}
+
+/// Counts a failed read of the `source` (`stats`, `depth` or `trades`) of the `symbol`.
+pub fn count_fetch_error(symbol: &Symbol, source: &'static str) {
+    *metrics()
+        .fetch_errors
+        .entry((symbol.clone(), source))
+        .or_default() += 1;
+}
+
+/// Records how long a `GET /api/v3/ticker/24hr` request of the price stats took.
+pub fn observe_price_stats_latency(latency: Duration) {
+    metrics().price_stats_latency.observe(latency.as_secs_f64());
+}
+
+/// Returns the metrics in the Prometheus text format, with the prices of the
+/// symbols watched by the `bot`.
+pub fn render(bot: &Bot) -> String {
+    let mut out = String::new();
+    let watched = bot
+        .watchlist()
+        .filter(|tracker| tracker.updated().is_some())
+        .map(|tracker| (tracker.symbol(), tracker.stats()))
+        .collect::<Vec<_>>();
+
+    header(
+        &mut out,
+        "alertabot_last_price",
+        "Last price of the watched symbol.",
+        "gauge",
+    );
+    for (symbol, stats) in &watched {
+        let _ = writeln!(
+            out,
+            "alertabot_last_price{{symbol=\"{symbol}\"}} {}",
+            stats.last_price
+        );
+    }
+    header(
+        &mut out,
+        "alertabot_price_change_percent",
+        "Price change of the watched symbol in the last 24h in percent.",
+        "gauge",
+    );
+    for (symbol, stats) in &watched {
+        let _ = writeln!(
+            out,
+            "alertabot_price_change_percent{{symbol=\"{symbol}\"}} {}",
+            stats.price_change_percent
+        );
+    }
+    header(
+        &mut out,
+        "alertabot_volume",
+        "Volume of the watched symbol in the last 24h in the base asset.",
+        "gauge",
+    );
+    for (symbol, stats) in &watched {
+        let _ = writeln!(
+            out,
+            "alertabot_volume{{symbol=\"{symbol}\"}} {}",
+            stats.volume
+        );
+    }
+
+    metrics().render(&mut out);
+    out
+}
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Escapes a label value of the text format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+#[test]
+fn metrics_text_format() {
+    let mut metrics = Metrics::new();
+    *metrics.alerts.entry("price").or_default() += 2;
+    *metrics
+        .fetch_errors
+        .entry((Symbol::new("ETHUSDT").unwrap(), "depth"))
+        .or_default() += 1;
+    metrics.price_stats_latency.observe(0.2);
+    metrics.price_stats_latency.observe(3.0);
+    metrics.price_stats_latency.observe(60.0);
+
+    let mut out = String::new();
+    metrics.render(&mut out);
+    let lines = out.lines().collect::<Vec<&str>>();
+    assert!(lines.contains(&"# TYPE alertabot_alerts_total counter"));
+    assert!(lines.contains(&r#"alertabot_alerts_total{target="price"} 2"#));
+    assert!(lines.contains(&r#"alertabot_fetch_errors_total{symbol="ETHUSDT",source="depth"} 1"#));
+    let histogram = "alertabot_price_stats_request_duration_seconds";
+    assert!(lines.contains(&format!(r#"{histogram}_bucket{{le="0.1"}} 0"#).as_str()));
+    assert!(lines.contains(&format!(r#"{histogram}_bucket{{le="0.25"}} 1"#).as_str()));
+    assert!(lines.contains(&format!(r#"{histogram}_bucket{{le="30"}} 2"#).as_str()));
+    assert!(lines.contains(&format!(r#"{histogram}_bucket{{le="+Inf"}} 3"#).as_str()));
+    assert!(lines.contains(&format!("{histogram}_count 3").as_str()));
+    assert_eq!(escape("say \"hi\"\n"), r#"say \"hi\"\n"#);
+}
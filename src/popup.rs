@@ -0,0 +1,525 @@
+use std::sync::Arc;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use tui::{
+    backend::Backend,
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{
+    bot::{Delivery, PriceLevel, PriceTrigger, TriggerCondition, TriggerTarget},
+    currency::Currency,
+    decimal::Decimal,
+    exchange::{ExchangeInfo, SymbolInfo},
+    symbol::Symbol,
+    theme::Theme,
+    tui::centered_rect,
+};
+
+/// Modal window drawn on top of the layout which receives all the key presses
+/// until it is closed.
+#[derive(Debug)]
+pub enum Popup {
+    /// Asks the user to confirm the `action`.
+    Confirm {
+        message: String,
+        action: PendingAction,
+    },
+    /// Shows an error until any key is pressed.
+    Error {
+        title: String,
+        message: String,
+    },
+    TriggerForm(TriggerForm),
+    SymbolPicker(SymbolPicker),
+}
+
+impl Popup {
+    const WIDTH: u16 = 56;
+
+    pub fn error(title: &str, message: String) -> Self {
+        Popup::Error {
+            title: title.to_owned(),
+            message,
+        }
+    }
+
+    /// Handles the key press returning what should happen with the popup.
+    pub fn process_key(&mut self, event: KeyEvent) -> Option<PopupResult> {
+        match self {
+            Popup::Confirm { action, .. } => match event.code {
+                KeyCode::Char('y' | 'Y') | KeyCode::Enter => Some(PopupResult::Confirmed(*action)),
+                KeyCode::Char('n' | 'N') | KeyCode::Esc => Some(PopupResult::Close),
+                _ => None,
+            },
+            Popup::Error { .. } => Some(PopupResult::Close),
+            Popup::TriggerForm(form) => form.process_key(event),
+            Popup::SymbolPicker(picker) => picker.process_key(event),
+        }
+    }
+
+    pub fn render<B: Backend>(&self, frame: &mut Frame<B>, theme: &Theme) {
+        let (title, text) = match self {
+            Popup::Confirm { message, .. } => (
+                "Confirm",
+                vec![
+                    Spans::from(message.as_str()),
+                    Spans::default(),
+                    Spans::from("[y] Yes  [n] No"),
+                ],
+            ),
+            Popup::Error { title, message } => (
+                title.as_str(),
+                vec![
+                    Spans::from(Span::styled(
+                        message.as_str(),
+                        Style::default().fg(theme.price_down),
+                    )),
+                    Spans::default(),
+                    Spans::from("Press any key to close"),
+                ],
+            ),
+            Popup::TriggerForm(form) => ("New Trigger", form.text(theme)),
+            Popup::SymbolPicker(picker) => ("Watch Symbol", picker.text(theme)),
+        };
+
+        // Long lines are wrapped so the height depends on the width
+        let inner_width = Self::WIDTH as usize - 2;
+        let height = text
+            .iter()
+            .map(|line| {
+                let line = line
+                    .0
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect::<String>();
+                wrapped_lines(&line, inner_width)
+            })
+            .sum::<usize>() as u16
+            + 2;
+        let area = centered_rect(Self::WIDTH, height, frame.size());
+        let paragraph = Paragraph::new(text)
+            .block(popup_block(title, theme))
+            .wrap(Wrap { trim: false });
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(paragraph, area);
+
+        let cursor = match self {
+            Popup::TriggerForm(form) => form.cursor(),
+            Popup::SymbolPicker(picker) => Some(picker.cursor()),
+            Popup::Confirm { .. } | Popup::Error { .. } => None,
+        };
+        if let Some((column, row)) = cursor {
+            let x = (area.x + 1 + column).min(area.right().saturating_sub(2));
+            frame.set_cursor(x, area.y + 1 + row);
+        }
+    }
+}
+
+/// Counts the lines the `line` takes when its words are wrapped at `width`.
+fn wrapped_lines(line: &str, width: usize) -> usize {
+    let mut lines = 1;
+    let mut column = 0;
+    for word in line.split(' ') {
+        let word_width = word.chars().count();
+        if column > 0 && column + 1 + word_width > width {
+            lines += 1;
+            column = 0;
+        } else if column > 0 {
+            column += 1;
+        }
+        column += word_width;
+        // Words longer than the line are broken up
+        while column > width {
+            lines += 1;
+            column -= width;
+        }
+    }
+    lines
+}
+
+/// Creates the block every popup is drawn in.
+pub fn popup_block<'a>(title: &'a str, theme: &Theme) -> Block<'a> {
+    Block::default()
+        .borders(Borders::all())
+        .border_type(BorderType::Double)
+        .border_style(theme.border(true))
+        .style(theme.text())
+        .title(title)
+}
+
+/// What happens after a key is pressed in a popup.
+#[derive(Debug, Clone)]
+pub enum PopupResult {
+    Close,
+    Confirmed(PendingAction),
+    Submitted(PriceTrigger),
+    /// The symbol was picked to be watched.
+    Picked(String),
+}
+
+/// Action which waits for a confirmation.
+#[derive(Debug, Clone, Copy)]
+pub enum PendingAction {
+    RemoveTrigger(usize),
+}
+
+/// Form for creating a trigger with every option.
+#[derive(Debug)]
+pub struct TriggerForm {
+    main_symbol: Symbol,
+    focused: FormField,
+
+    symbol: String,
+    /// Index into [`TriggerForm::CONDITIONS`].
+    condition: usize,
+    level: String,
+    /// Currency the level is in, empty for the quote asset of the symbol.
+    currency: String,
+    delivery: Delivery,
+
+    /// Why the trigger couldn't be created.
+    error: Option<String>,
+}
+
+impl TriggerForm {
+    const CONDITIONS: &[(TriggerTarget, TriggerCondition)] = &[
+        (TriggerTarget::Price, TriggerCondition::HigherEq),
+        (TriggerTarget::Price, TriggerCondition::LowerEq),
+        (TriggerTarget::Imbalance, TriggerCondition::HigherEq),
+        (TriggerTarget::Imbalance, TriggerCondition::LowerEq),
+        (TriggerTarget::Trade, TriggerCondition::HigherEq),
+    ];
+    const LABEL_WIDTH: usize = 11;
+
+    /// Creates an empty form for the triggers of the `main_symbol`.
+    pub fn new(main_symbol: Symbol) -> Self {
+        Self {
+            symbol: main_symbol.to_string(),
+            main_symbol,
+            focused: FormField::Level,
+            condition: 0,
+            level: String::new(),
+            currency: String::new(),
+            delivery: Delivery::default(),
+            error: None,
+        }
+    }
+
+    fn process_key(&mut self, event: KeyEvent) -> Option<PopupResult> {
+        match event.code {
+            KeyCode::Esc => return Some(PopupResult::Close),
+            KeyCode::Enter => match self.trigger() {
+                Ok(trigger) => return Some(PopupResult::Submitted(trigger)),
+                Err(err) => self.error = Some(err),
+            },
+            KeyCode::Tab | KeyCode::Down => self.focused = self.focused.offset(1),
+            KeyCode::BackTab | KeyCode::Up => {
+                self.focused = self.focused.offset(FormField::ALL.len() - 1)
+            }
+            KeyCode::Left => self.cycle(false),
+            KeyCode::Right => self.cycle(true),
+            KeyCode::Backspace => {
+                if let Some(text) = self.text_field() {
+                    text.pop();
+                }
+            }
+            KeyCode::Char(c) if !c.is_control() => match self.text_field() {
+                Some(text) => text.push(c),
+                None if c == ' ' => self.cycle(true),
+                None => (),
+            },
+            _ => (),
+        }
+        None
+    }
+
+    /// Returns the content of the focused field if it is typed in.
+    fn text_field(&mut self) -> Option<&mut String> {
+        match self.focused {
+            FormField::Symbol => Some(&mut self.symbol),
+            FormField::Level => Some(&mut self.level),
+            FormField::Currency => Some(&mut self.currency),
+            FormField::Condition | FormField::Delivery => None,
+        }
+    }
+
+    /// Switches the focused choice field to the next or previous option.
+    fn cycle(&mut self, forward: bool) {
+        let step = |index: usize, len: usize| {
+            if forward {
+                (index + 1) % len
+            } else {
+                (index + len - 1) % len
+            }
+        };
+        match self.focused {
+            FormField::Condition => {
+                self.condition = step(self.condition, Self::CONDITIONS.len());
+            }
+            FormField::Delivery => {
+                let index = Delivery::ALL.iter().position(|&d| d == self.delivery);
+                self.delivery = Delivery::ALL[step(index.unwrap_or(0), Delivery::ALL.len())];
+            }
+            FormField::Symbol | FormField::Level | FormField::Currency => (),
+        }
+    }
+
+    /// Creates the trigger from the form fields.
+    fn trigger(&self) -> Result<PriceTrigger, String> {
+        let (target, condition) = Self::CONDITIONS[self.condition];
+        let level = self
+            .level
+            .trim()
+            .parse::<Decimal>()
+            .map_err(|_| format!("invalid level '{}'", self.level.trim()))?;
+
+        let symbol = Symbol::new(&self.symbol)
+            .map_err(|_| format!("invalid symbol '{}'", self.symbol.trim()))?;
+
+        let mut trigger = PriceTrigger::with_target(PriceLevel(level), target, condition);
+        if symbol != self.main_symbol {
+            trigger.symbol = Some(symbol);
+        }
+        if !self.currency.trim().is_empty() {
+            let currency = Currency::new(&self.currency)
+                .map_err(|_| format!("invalid currency '{}'", self.currency.trim()))?;
+            trigger.currency = Some(currency);
+        }
+        trigger.delivery = self.delivery;
+        trigger.validate()?;
+        Ok(trigger)
+    }
+
+    fn text(&self, theme: &Theme) -> Vec<Spans<'static>> {
+        let (target, condition) = Self::CONDITIONS[self.condition];
+        let mut text = FormField::ALL
+            .iter()
+            .map(|&field| {
+                let value = match field {
+                    FormField::Symbol => self.symbol.clone(),
+                    FormField::Condition => format!("< {target} {condition} >"),
+                    FormField::Level => self.level.clone(),
+                    FormField::Currency => self.currency.clone(),
+                    FormField::Delivery => format!("< {} >", self.delivery),
+                };
+                let style = if field == self.focused {
+                    Style::default()
+                        .fg(theme.highlight)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    theme.text()
+                };
+                Spans::from(vec![
+                    Span::styled(
+                        format!("{:<w$}", field.label(), w = Self::LABEL_WIDTH),
+                        style,
+                    ),
+                    Span::styled(value, style),
+                ])
+            })
+            .collect::<Vec<Spans>>();
+
+        text.push(Spans::default());
+        match &self.error {
+            Some(err) => text.push(Spans::from(Span::styled(
+                format!("error: {err}"),
+                Style::default().fg(theme.price_down),
+            ))),
+            None => text.push(Spans::from(
+                "Enter: add  Esc: cancel  Tab: next  Left/Right: change",
+            )),
+        }
+        text
+    }
+
+    /// Position of the cursor inside the popup when a text field is focused.
+    fn cursor(&self) -> Option<(u16, u16)> {
+        let value = match self.focused {
+            FormField::Symbol => &self.symbol,
+            FormField::Level => &self.level,
+            FormField::Currency => &self.currency,
+            FormField::Condition | FormField::Delivery => return None,
+        };
+        let row = FormField::ALL.iter().position(|&f| f == self.focused)?;
+        Some((
+            (Self::LABEL_WIDTH + value.chars().count()) as u16,
+            row as u16,
+        ))
+    }
+}
+
+/// Searchable list of the symbols listed on the exchange.
+#[derive(Debug)]
+pub struct SymbolPicker {
+    exchange: Arc<ExchangeInfo>,
+    query: String,
+    /// Index of the selected symbol among the matching ones.
+    selected: usize,
+}
+
+impl SymbolPicker {
+    /// Number of the matching symbols listed at once.
+    const ROWS: usize = 10;
+    const PROMPT: &str = "Search: ";
+
+    pub fn new(exchange: Arc<ExchangeInfo>) -> Self {
+        Self {
+            exchange,
+            query: String::new(),
+            selected: 0,
+        }
+    }
+
+    fn matches(&self) -> Vec<&SymbolInfo> {
+        self.exchange.search(&self.query)
+    }
+
+    fn process_key(&mut self, event: KeyEvent) -> Option<PopupResult> {
+        match event.code {
+            KeyCode::Esc => return Some(PopupResult::Close),
+            KeyCode::Enter => {
+                let picked = self.matches().get(self.selected)?.symbol.clone();
+                return Some(PopupResult::Picked(picked));
+            }
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => {
+                let last = self.matches().len().saturating_sub(1);
+                self.selected = (self.selected + 1).min(last);
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.selected = 0;
+            }
+            KeyCode::Char(c) if c.is_ascii_alphanumeric() => {
+                self.query.push(c.to_ascii_uppercase());
+                self.selected = 0;
+            }
+            _ => (),
+        }
+        None
+    }
+
+    fn text(&self, theme: &Theme) -> Vec<Spans<'static>> {
+        let matches = self.matches();
+        let row = |symbol: &str, pair: &str, tick: &str, lot: &str| {
+            format!("{symbol:<13}{pair:<14}{tick:<12}{lot}")
+        };
+
+        let mut text = vec![
+            Spans::from(format!("{}{}", Self::PROMPT, self.query)),
+            Spans::default(),
+            Spans::from(Span::styled(
+                row("Symbol", "Pair", "Tick", "Lot"),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+        ];
+        // The list scrolls so the selected symbol stays visible
+        let first = self.selected.saturating_sub(Self::ROWS - 1);
+        for (index, info) in matches.iter().enumerate().skip(first).take(Self::ROWS) {
+            let style = if index == self.selected {
+                Style::default()
+                    .fg(theme.highlight)
+                    .add_modifier(Modifier::BOLD)
+            } else if info.is_trading() {
+                theme.text()
+            } else {
+                theme.text().add_modifier(Modifier::DIM)
+            };
+            let line = row(
+                &info.symbol,
+                &info.pair(),
+                info.tick_size.as_deref().unwrap_or("-"),
+                info.lot_size.as_deref().unwrap_or("-"),
+            );
+            text.push(Spans::from(Span::styled(line, style)));
+        }
+        // The popup keeps its height while typing
+        let shown = matches.len().saturating_sub(first).min(Self::ROWS);
+        text.extend((shown..Self::ROWS).map(|_| Spans::default()));
+
+        text.push(Spans::default());
+        text.push(Spans::from(format!(
+            "{} found  Enter: watch  Esc: cancel  Up/Down: select",
+            matches.len()
+        )));
+        text
+    }
+
+    /// Position of the cursor at the end of the query.
+    fn cursor(&self) -> (u16, u16) {
+        ((Self::PROMPT.len() + self.query.len()) as u16, 0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FormField {
+    Symbol,
+    Condition,
+    Level,
+    Currency,
+    Delivery,
+}
+
+impl FormField {
+    const ALL: &[FormField] = &[
+        FormField::Symbol,
+        FormField::Condition,
+        FormField::Level,
+        FormField::Currency,
+        FormField::Delivery,
+    ];
+
+    fn offset(self, offset: usize) -> FormField {
+        let index = Self::ALL.iter().position(|&f| f == self).unwrap();
+        Self::ALL[(index + offset) % Self::ALL.len()]
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            FormField::Symbol => "Symbol:",
+            FormField::Condition => "Condition:",
+            FormField::Level => "Level:",
+            FormField::Currency => "Currency:",
+            FormField::Delivery => "Delivery:",
+        }
+    }
+}
+
+#[test]
+fn trigger_form_validation() {
+    use crossterm::event::KeyModifiers;
+
+    let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+    let mut form = TriggerForm::new(Symbol::new("ETHUSDT").unwrap());
+    assert!(form.process_key(key(KeyCode::Enter)).is_none());
+    assert!(form.error.is_some());
+
+    form.process_key(key(KeyCode::Char('9')));
+    form.process_key(key(KeyCode::Tab));
+    form.process_key(key(KeyCode::Tab));
+    form.process_key(key(KeyCode::Right));
+    let trigger = match form.process_key(key(KeyCode::Enter)) {
+        Some(PopupResult::Submitted(trigger)) => trigger,
+        result => panic!("unexpected result {result:?}"),
+    };
+    assert_eq!(trigger.delivery, Delivery::Bell);
+    assert!(trigger.symbol.is_none());
+    assert!(trigger.currency.is_none());
+
+    form.currency = "eur".to_owned();
+    assert_eq!(
+        form.trigger().unwrap().currency,
+        Some(Currency::new("EUR").unwrap())
+    );
+    form.currency.clear();
+
+    // Only price triggers can be set on the other symbols
+    form.symbol = "btcusdt".to_owned();
+    form.condition = 2;
+    assert!(form.trigger().is_err());
+}
@@ -1,94 +1,137 @@
-use crate::error::Result;
-use std::{fs, io};
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use crate::{bot::PriceTrigger, error::Result, symbol::Symbol};
 
 const SAVE: &str = "bot_data.json";
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+/// State of the bot which is kept between the runs.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct SaveData {
-    zones: Vec<PriceLevelData>,
+    pub triggers: Vec<PriceTrigger>,
+    /// Watched symbols other than the main one.
+    pub watchlist: Vec<Symbol>,
 }
 
-impl SaveData {
-    fn empty() -> Self {
-        Self { zones: Vec::new() }
-    }
+/// Saves which are written in batches so changing the triggers doesn't write
+/// the save file every time.
+///
+/// It is global so the panic hook can still write the pending save.
+static SAVES: Mutex<Saves> = Mutex::new(Saves {
+    pending: None,
+    written: None,
+});
+
+struct Saves {
+    /// Serialized data waiting to be written.
+    pending: Option<String>,
+    /// Serialized data which is in the save file.
+    written: Option<String>,
+}
+
+fn save_path() -> Result<PathBuf> {
+    Ok(std::env::current_exe()?.with_file_name(SAVE))
+}
+
+fn saves() -> MutexGuard<'static, Saves> {
+    // The saves stay consistent even if a thread panicked while holding them
+    SAVES.lock().unwrap_or_else(PoisonError::into_inner)
+}
 
-    pub fn data(self) -> Vec<Zone> {
-        let mut zones = Vec::with_capacity(self.zones.len());
-        for z in self.zones {
-            zones.push(z.into());
+/// Loads the save file found next to the executable.
+///
+/// If the save file is **not found** `None` is returned.
+pub fn load_save_file() -> Result<Option<SaveData>> {
+    match fs::read(save_path()?) {
+        Ok(data) => {
+            let data: SaveData = serde_json::from_slice(&data)?;
+            saves().written = Some(serde_json::to_string_pretty(&data)?);
+            Ok(Some(data))
         }
-        zones
+        Err(err) => match err.kind() {
+            io::ErrorKind::NotFound => Ok(None),
+            _ => Err(err.into()),
+        },
     }
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
-struct PriceLevelData(f64);
+/// Remembers `data` to be written by the next [`flush`] if it differs from the
+/// data in the save file, replacing the data scheduled before.
+pub fn schedule(data: &SaveData) -> Result<()> {
+    let serialized = serde_json::to_string_pretty(data)?;
+    let mut saves = saves();
+    saves.pending = (saves.written.as_ref() != Some(&serialized)).then_some(serialized);
+    Ok(())
+}
 
-/// Searches for the save file.
+/// Writes the pending save if there is one.
 ///
-/// Possibilities:
-/// - If the save file is **found** the function returns the data.
-/// - If the save file is **not found** the function creates a new one.
-pub fn load_create_save_file() -> Result<SaveData> {
-    let path = &format!(
-        "{}/{}",
-        std::env::current_exe()?
-            .parent()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            // If the program is run from the source it would have these char prefixes
-            .trim_start_matches("\\\\?\\"),
-        SAVE
-    );
+/// The data is first written to a temporary file which then replaces the save
+/// file so a crash while writing can't corrupt it.
+pub fn flush() -> Result<()> {
+    flush_saves(&mut saves())
+}
 
-    match fs::read(path) {
-        Ok(data) =>  {
-            println!("Save file found! Loading ...");
-            Ok(serde_json::from_slice(&data)?)
-        },
-        Err(err) => match err.kind() {
-            io::ErrorKind::NotFound => {
-                println!("Save file NOT found! Creating a new one ...");
-                let form = serde_json::to_string(&SaveData::empty())?;
-                fs::write(path, form)?;
-                Ok(SaveData::empty())
-            },
-            _ => Err(err.into()),
-        }
+/// Same as [`flush`] but gives up instead of waiting if the saves are being
+/// used which could never end when called from a panicking thread.
+pub fn try_flush() -> Result<()> {
+    match SAVES.try_lock() {
+        Ok(mut saves) => flush_saves(&mut saves),
+        Err(_) => Ok(()),
     }
 }
 
-/// Saves `data` into the save file. 
-/// 
-/// If the save file is not found it creates a new file with memorized data.
-pub fn save_data(data: &SaveData) -> Result<()> {
-    let path = &format!(
-        "{}/{}",
-        std::env::current_exe()?
-            .parent()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            // If the program is run from the source it would have these char prefixes
-            .trim_start_matches("\\\\?\\"),
-        SAVE
-    );
-    let serialized = serde_json::to_string(data)?;
-
-    // Error check in case file is missing
-    if let Err(e) = fs::File::open(path) {
-        match e.kind() {
-            io::ErrorKind::NotFound => println!(
-                "The save file is missing! \
-                    Creating a new one with memorized data."
-            ),
-            _ => return Err(e.into()),
-        }
+fn flush_saves(saves: &mut Saves) -> Result<()> {
+    if let Some(data) = saves.pending.take() {
+        let path = save_path()?;
+        let temporary = path.with_extension("json.tmp");
+        fs::write(&temporary, &data)?;
+        fs::rename(temporary, path)?;
+        saves.written = Some(data);
     }
+    Ok(())
+}
 
-    fs::write(SAVE, serialized)?;
+#[test]
+fn save_data_round_trip() {
+    use crate::{
+        bot::{Delivery, PriceLevel, TriggerCondition, TriggerTarget},
+        decimal::Decimal,
+    };
 
-    Ok(())
-}
\ No newline at end of file
+    let mut trigger = PriceTrigger::with_target(
+        PriceLevel(Decimal::new(25, 0)),
+        TriggerTarget::Imbalance,
+        TriggerCondition::LowerEq,
+    );
+    trigger.delivery = Delivery::Bell;
+    let mut other = PriceTrigger::with_target(
+        PriceLevel(Decimal::new(7, 2)),
+        TriggerTarget::Price,
+        TriggerCondition::HigherEq,
+    );
+    other.symbol = Some(Symbol::new("ETHBTC").unwrap());
+    other.enabled = false;
+    let data = SaveData {
+        triggers: vec![trigger, other],
+        watchlist: vec![Symbol::new("ETHBTC").unwrap()],
+    };
+
+    let json = serde_json::to_string(&data).unwrap();
+    let loaded: SaveData = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+    assert_eq!(loaded.triggers[0].target, TriggerTarget::Imbalance);
+    assert_eq!(
+        loaded.triggers[1].symbol,
+        Some(Symbol::new("ETHBTC").unwrap())
+    );
+    assert!(!loaded.triggers[1].enabled);
+
+    // Older save files without some fields still load
+    let empty: SaveData = serde_json::from_str("{}").unwrap();
+    assert!(empty.triggers.is_empty());
+}
@@ -0,0 +1,68 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+#[cfg(unix)]
+use signal_hook::consts::SIGHUP;
+use signal_hook::consts::{SIGINT, SIGTERM};
+
+use crate::error::Result;
+
+#[cfg(unix)]
+const SHUTDOWN_SIGNALS: &[i32] = &[SIGINT, SIGTERM, SIGHUP];
+#[cfg(not(unix))]
+const SHUTDOWN_SIGNALS: &[i32] = &[SIGINT, SIGTERM];
+/// Signals shutting down the daemon which reloads on SIGHUP instead.
+const DAEMON_SHUTDOWN_SIGNALS: &[i32] = &[SIGINT, SIGTERM];
+
+/// Records the signals asking the bot to shut down.
+///
+/// The terminal is in the raw mode so pressing Ctrl-C doesn't send SIGINT and is
+/// handled as a key instead.
+pub struct ShutdownSignal(Arc<AtomicBool>);
+
+impl ShutdownSignal {
+    /// Starts listening for SIGINT, SIGTERM and SIGHUP.
+    pub fn register() -> Result<Self> {
+        Self::register_signals(SHUTDOWN_SIGNALS)
+    }
+
+    /// Starts listening for SIGINT and SIGTERM, leaving SIGHUP to the [`ReloadSignal`].
+    pub fn register_daemon() -> Result<Self> {
+        Self::register_signals(DAEMON_SHUTDOWN_SIGNALS)
+    }
+
+    /// A second signal terminates the bot right away in case the shutdown hangs.
+    fn register_signals(signals: &[i32]) -> Result<Self> {
+        let received = Arc::new(AtomicBool::new(false));
+        for &signal in signals {
+            // Registered first so it only terminates once the flag was set before
+            signal_hook::flag::register_conditional_shutdown(signal, 1, received.clone())?;
+            signal_hook::flag::register(signal, received.clone())?;
+        }
+        Ok(Self(received))
+    }
+
+    pub fn received(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Records SIGHUP asking the daemon to reload its config and saved state.
+pub struct ReloadSignal(Arc<AtomicBool>);
+
+impl ReloadSignal {
+    /// Starts listening for SIGHUP, which never arrives on other platforms than Unix.
+    pub fn register() -> Result<Self> {
+        let received = Arc::new(AtomicBool::new(false));
+        #[cfg(unix)]
+        signal_hook::flag::register(SIGHUP, received.clone())?;
+        Ok(Self(received))
+    }
+
+    /// Returns whether the signal was received since this was last called.
+    pub fn take(&self) -> bool {
+        self.0.swap(false, Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,197 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    process::{Command, Stdio},
+    thread,
+    time::UNIX_EPOCH,
+};
+
+use crate::{
+    bot::{Alert, Delivery},
+    error::{Error, Result},
+};
+
+/// Where the alerts are delivered besides the TUI, configured in the `[sinks]`
+/// section of the config file.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SinkConfig {
+    /// Whether the terminal bell is rung for the triggers with the `bell` delivery.
+    pub bell: bool,
+    /// File every alert is appended to.
+    pub file: Option<PathBuf>,
+    /// Shell command run for every alert with the alert in `ALERTABOT_ALERT`.
+    pub command: Option<String>,
+}
+
+impl Default for SinkConfig {
+    fn default() -> Self {
+        Self {
+            bell: true,
+            file: None,
+            command: None,
+        }
+    }
+}
+
+/// Delivers the alerts to the configured sinks.
+pub struct AlertSinks {
+    bell: bool,
+    file: Option<BufWriter<File>>,
+    command: Option<String>,
+    /// Whether the bell should be rung by the next flush.
+    ring: bool,
+    /// First error of the delivered alerts, returned by the next flush.
+    error: Option<Error>,
+}
+
+impl AlertSinks {
+    pub fn new(config: &SinkConfig) -> Result<Self> {
+        let file = match &config.file {
+            Some(path) => {
+                let file = OpenOptions::new().create(true).append(true).open(path)?;
+                Some(BufWriter::new(file))
+            }
+            None => None,
+        };
+        Ok(Self {
+            bell: config.bell,
+            file,
+            command: config.command.clone(),
+            ring: false,
+            error: None,
+        })
+    }
+
+    /// Sends the `alert` to every sink, the buffered sinks are written by [`AlertSinks::flush`].
+    ///
+    /// A failing sink doesn't stop the other sinks or the next alerts, its first
+    /// error is returned by the flush instead.
+    pub fn deliver(&mut self, alert: &Alert) {
+        self.ring |= self.bell && alert.delivery == Delivery::Bell;
+
+        let seconds = alert
+            .time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut errors = Vec::new();
+        if let Some(file) = &mut self.file {
+            if let Err(err) = writeln!(file, "{} {alert}", format_timestamp(seconds)) {
+                errors.push(Error::from(err));
+            }
+        }
+        if let Some(command) = &self.command {
+            let spawned = shell(command)
+                .env("ALERTABOT_ALERT", alert.to_string())
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn();
+            match spawned {
+                Ok(mut child) => {
+                    // Waited for in another thread so slow commands don't block the bot
+                    thread::spawn(move || child.wait());
+                }
+                Err(err) => errors.push(Error::from(err).with_context(command.as_str())),
+            }
+        }
+        if self.error.is_none() {
+            self.error = errors.into_iter().next();
+        }
+    }
+
+    /// Rings the bell for the delivered alerts and writes the buffered ones, returning
+    /// the first error of the sinks since the last flush.
+    pub fn flush(&mut self) -> Result<()> {
+        let delivery_error = self.error.take();
+        if std::mem::take(&mut self.ring) {
+            let mut stdout = io::stdout();
+            stdout.write_all(b"\x07")?;
+            stdout.flush()?;
+        }
+        if let Some(file) = &mut self.file {
+            file.flush()?;
+        }
+        delivery_error.map_or(Ok(()), Err)
+    }
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+/// Formats the seconds since the Unix epoch as `YYYY-MM-DD HH:MM:SS` in UTC.
+pub fn format_timestamp(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let time = seconds % 86_400;
+
+    // Converts the days since the epoch to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+#[test]
+fn timestamp_format() {
+    assert_eq!(format_timestamp(0), "1970-01-01 00:00:00");
+    assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00:00");
+    assert_eq!(format_timestamp(1_700_000_000), "2023-11-14 22:13:20");
+}
+
+#[test]
+fn failing_command_sink() {
+    use crate::bot::AlertKind;
+    use std::time::{Duration, SystemTime};
+
+    let path = std::env::temp_dir().join(format!("alertabot-sinks-{}.log", std::process::id()));
+    let mut sinks = AlertSinks::new(&SinkConfig {
+        bell: false,
+        file: Some(path.clone()),
+        // Commands with a NUL byte can't be started
+        command: Some("true\0".to_owned()),
+    })
+    .unwrap();
+    let alert = Alert {
+        kind: AlertKind::Stale(Duration::from_secs(30)),
+        symbol: None,
+        currency: None,
+        delivery: Delivery::Tui,
+        time: SystemTime::now(),
+    };
+    sinks.deliver(&alert);
+    sinks.deliver(&alert);
+    assert!(sinks.flush().is_err());
+    assert!(sinks.flush().is_ok());
+
+    // The file sink still got every alert
+    let written = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(written.lines().count(), 2);
+}
@@ -0,0 +1,122 @@
+use std::{fmt::Display, str::FromStr, sync::Arc};
+
+use crate::error::{Error, Result};
+
+/// Assets the symbols are commonly quoted in, used to split a symbol into its
+/// base and quote assets when the exchange info isn't known.
+const QUOTE_ASSETS: &[&str] = &[
+    "USDT", "FDUSD", "BUSD", "USDC", "TUSD", "USDP", "DAI", "BTC", "ETH", "BNB", "XRP", "TRX",
+    "DOGE", "DOT", "EUR", "GBP", "TRY", "BRL", "AUD", "JPY", "RUB", "UAH", "ZAR", "PLN", "RON",
+    "ARS", "NGN", "BIDR", "IDRT",
+];
+
+/// Longest symbol accepted, Binance symbols have at most 20 characters.
+const MAX_LENGTH: usize = 20;
+
+/// Name of a market like `ETHUSDT`.
+///
+/// The name is always uppercase and made only of ASCII letters and digits. It is
+/// shared so cloning a symbol is cheap.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    /// Creates a symbol from user input, e.g. `ethusdt` becomes `ETHUSDT`.
+    pub fn new(name: &str) -> Result<Symbol> {
+        let name = name.trim().to_uppercase();
+        Self::check_name(&name)?;
+        Ok(Symbol(name.into()))
+    }
+
+    /// Checks that the `name` can be a symbol before asking the exchange about it.
+    fn check_name(name: &str) -> Result<()> {
+        if name.is_empty()
+            || name.len() > MAX_LENGTH
+            || !name.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            return Err(Error::invalid_symbol(name));
+        }
+        Ok(())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Splits the symbol into its base and quote assets, e.g. `ETHBTC` into `ETH` and
+    /// `BTC`, or returns `None` if it isn't quoted in one of the common assets.
+    ///
+    /// The exchange info knows the assets of every symbol so it should be preferred.
+    pub fn split(&self) -> Option<(&str, &str)> {
+        QUOTE_ASSETS
+            .iter()
+            .filter(|quote| self.0.len() > quote.len() && self.0.ends_with(*quote))
+            .max_by_key(|quote| quote.len())
+            .map(|quote| self.0.split_at(self.0.len() - quote.len()))
+    }
+
+    pub fn base(&self) -> Option<&str> {
+        self.split().map(|(base, _)| base)
+    }
+
+    pub fn quote(&self) -> Option<&str> {
+        self.split().map(|(_, quote)| quote)
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for Symbol {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Symbol::new(s)
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> String {
+        symbol.0.to_string()
+    }
+}
+
+impl serde::Serialize for Symbol {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Symbol {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Symbol::new(&name).map_err(serde::de::Error::custom)
+    }
+}
+
+#[test]
+fn symbol_names() {
+    let symbol = Symbol::new(" ethusdt").unwrap();
+    assert_eq!(symbol.as_str(), "ETHUSDT");
+    assert_eq!(symbol, "ETHUSDT".parse().unwrap());
+    assert_eq!(symbol.split(), Some(("ETH", "USDT")));
+    assert_eq!(Symbol::new("ETHBTC").unwrap().quote(), Some("BTC"));
+    assert_eq!(Symbol::new("USDTDAI").unwrap().base(), Some("USDT"));
+    assert_eq!(Symbol::new("USDT").unwrap().split(), None);
+    assert!(Symbol::new("").is_err());
+    assert!(Symbol::new("ETH-USDT").is_err());
+    assert!(Symbol::new("ETHUSDTETHUSDTETHUSDT").is_err());
+
+    assert_eq!(serde_json::to_string(&symbol).unwrap(), r#""ETHUSDT""#);
+    let parsed: Symbol = serde_json::from_str(r#""btcusdt""#).unwrap();
+    assert_eq!(parsed.as_str(), "BTCUSDT");
+    assert!(serde_json::from_str::<Symbol>(r#""BTC/USDT""#).is_err());
+}
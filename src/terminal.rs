@@ -0,0 +1,59 @@
+use std::{
+    io,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crossterm::{
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use crate::error::Result;
+
+/// Whether the terminal was set up for the TUI and not restored yet.
+static ENTERED: AtomicBool = AtomicBool::new(false);
+
+/// Switches the terminal into the raw mode and the alternate screen used by the TUI
+/// and restores it when dropped, also while unwinding from a panic or an error.
+pub struct TerminalGuard(());
+
+impl TerminalGuard {
+    pub fn enter() -> Result<Self> {
+        // Created first so the terminal is restored if only some of the setup succeeds
+        let guard = Self(());
+        ENTERED.store(true, Ordering::Relaxed);
+        crossterm::execute!(
+            io::stdout(),
+            EnterAlternateScreen,
+            EnableBracketedPaste,
+            EnableMouseCapture
+        )?;
+        terminal::enable_raw_mode()?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Leaves the raw mode and the alternate screen if the TUI entered them.
+///
+/// Only the first call restores the terminal so the panic hook can restore it before
+/// printing the panic message, the guard doing nothing while unwinding, and commands
+/// without the TUI like the daemon don't get any escape codes in their output.
+pub fn restore() {
+    if !ENTERED.swap(false, Ordering::Relaxed) {
+        return;
+    }
+    // Nothing else can be done if the terminal can't be restored
+    let _ = terminal::disable_raw_mode();
+    let _ = crossterm::execute!(
+        io::stdout(),
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        LeaveAlternateScreen
+    );
+}
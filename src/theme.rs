@@ -0,0 +1,232 @@
+use std::{fs, io, str::FromStr};
+
+use serde::{de, Deserialize, Deserializer};
+use tui::style::{Color, Modifier, Style};
+
+use crate::error::{Error, Result};
+
+const THEMES_DIR: &str = "themes";
+
+/// Colours used for drawing the TUI.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub text: Color,
+    pub border: Color,
+    pub focused_border: Color,
+    pub price_up: Color,
+    pub price_down: Color,
+    pub alert: Color,
+    pub new_alert: Color,
+    pub flash: Color,
+    pub disabled: Color,
+    pub highlight: Color,
+}
+
+impl Theme {
+    pub const DARK: Theme = Theme {
+        text: Color::Reset,
+        border: Color::Gray,
+        focused_border: Color::Cyan,
+        price_up: Color::Green,
+        price_down: Color::Red,
+        alert: Color::Yellow,
+        new_alert: Color::LightYellow,
+        flash: Color::LightRed,
+        disabled: Color::DarkGray,
+        highlight: Color::Cyan,
+    };
+
+    pub const LIGHT: Theme = Theme {
+        text: Color::Black,
+        border: Color::DarkGray,
+        focused_border: Color::Blue,
+        price_up: Color::Green,
+        price_down: Color::Red,
+        alert: Color::Magenta,
+        new_alert: Color::LightMagenta,
+        flash: Color::Red,
+        disabled: Color::Gray,
+        highlight: Color::Blue,
+    };
+
+    pub const HIGH_CONTRAST: Theme = Theme {
+        text: Color::White,
+        border: Color::White,
+        focused_border: Color::Yellow,
+        price_up: Color::LightGreen,
+        price_down: Color::LightRed,
+        alert: Color::LightYellow,
+        new_alert: Color::Yellow,
+        flash: Color::LightRed,
+        disabled: Color::Gray,
+        highlight: Color::Yellow,
+    };
+
+    fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Self::DARK),
+            "light" => Some(Self::LIGHT),
+            "high-contrast" => Some(Self::HIGH_CONTRAST),
+            _ => None,
+        }
+    }
+
+    /// Loads the built-in theme called `name` or the user theme from the
+    /// `themes/<name>.toml` file next to the executable.
+    pub fn load(name: &str) -> Result<Theme> {
+        if let Some(theme) = Self::builtin(name) {
+            return Ok(theme);
+        }
+
+        let path = std::env::current_exe()?
+            .with_file_name(THEMES_DIR)
+            .join(format!("{name}.toml"));
+        let data = match fs::read_to_string(&path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                let message = format!("theme '{name}' not found at {}", path.display());
+                return Err(Error::config(message));
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let user: UserTheme = toml::from_str(&data)?;
+        user.into_theme()
+    }
+
+    pub fn text(&self) -> Style {
+        Style::default().fg(self.text)
+    }
+
+    pub fn border(&self, focused: bool) -> Style {
+        if focused {
+            Style::default()
+                .fg(self.focused_border)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(self.border)
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DARK
+    }
+}
+
+/// Theme file where missing colours are taken from the `base` built-in theme.
+///
+/// ```toml
+/// base = "light"
+/// price_up = "#00aa00"
+/// alert = "magenta"
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UserTheme {
+    base: Option<String>,
+    text: Option<ThemeColor>,
+    border: Option<ThemeColor>,
+    focused_border: Option<ThemeColor>,
+    price_up: Option<ThemeColor>,
+    price_down: Option<ThemeColor>,
+    alert: Option<ThemeColor>,
+    new_alert: Option<ThemeColor>,
+    flash: Option<ThemeColor>,
+    disabled: Option<ThemeColor>,
+    highlight: Option<ThemeColor>,
+}
+
+impl UserTheme {
+    fn into_theme(self) -> Result<Theme> {
+        let base = self.base.as_deref().unwrap_or("dark");
+        let base = Theme::builtin(base)
+            .ok_or_else(|| Error::config(format!("unknown base theme '{base}'")))?;
+        let pick = |color: Option<ThemeColor>, default: Color| color.map_or(default, |c| c.0);
+
+        Ok(Theme {
+            text: pick(self.text, base.text),
+            border: pick(self.border, base.border),
+            focused_border: pick(self.focused_border, base.focused_border),
+            price_up: pick(self.price_up, base.price_up),
+            price_down: pick(self.price_down, base.price_down),
+            alert: pick(self.alert, base.alert),
+            new_alert: pick(self.new_alert, base.new_alert),
+            flash: pick(self.flash, base.flash),
+            disabled: pick(self.disabled, base.disabled),
+            highlight: pick(self.highlight, base.highlight),
+        })
+    }
+}
+
+/// Colour written as a name (`light-red`), a `#rrggbb` hex code or a 256 colour index.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ThemeColor(Color);
+
+impl FromStr for ThemeColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if let Some(hex) = s.strip_prefix('#') {
+            let rgb = u32::from_str_radix(hex, 16)
+                .ok()
+                .filter(|_| hex.len() == 6)
+                .ok_or_else(|| format!("invalid hex colour '{s}'"))?;
+            let [_, r, g, b] = rgb.to_be_bytes();
+            return Ok(ThemeColor(Color::Rgb(r, g, b)));
+        }
+        if let Ok(index) = s.parse::<u8>() {
+            return Ok(ThemeColor(Color::Indexed(index)));
+        }
+
+        let color = match s.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+            "reset" | "default" => Color::Reset,
+            "black" => Color::Black,
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "gray" | "grey" => Color::Gray,
+            "darkgray" | "darkgrey" => Color::DarkGray,
+            "lightred" => Color::LightRed,
+            "lightgreen" => Color::LightGreen,
+            "lightyellow" => Color::LightYellow,
+            "lightblue" => Color::LightBlue,
+            "lightmagenta" => Color::LightMagenta,
+            "lightcyan" => Color::LightCyan,
+            "white" => Color::White,
+            _ => return Err(format!("unknown colour '{s}'")),
+        };
+        Ok(ThemeColor(color))
+    }
+}
+
+impl<'de> Deserialize<'de> for ThemeColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+#[test]
+fn parse_user_theme() {
+    let user: UserTheme = toml::from_str(
+        r##"
+        base = "light"
+        price_up = "#00aa00"
+        alert = "Light-Magenta"
+        flash = "208"
+        "##,
+    )
+    .unwrap();
+    let theme = user.into_theme().unwrap();
+    assert_eq!(theme.price_up, Color::Rgb(0, 0xaa, 0));
+    assert_eq!(theme.alert, Color::LightMagenta);
+    assert_eq!(theme.flash, Color::Indexed(208));
+    assert_eq!(theme.text, Theme::LIGHT.text);
+
+    assert!("#12345".parse::<ThemeColor>().is_err());
+    assert!("purple".parse::<ThemeColor>().is_err());
+}
@@ -1,161 +1,839 @@
-use std::sync::Arc;
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::HashMap,
+    fmt::Display,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
 
-use binance::model::PriceStats;
+use binance::model::OrderBook;
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use tui::{
     backend::Backend,
-    layout::{Alignment, Constraint, Corner, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    layout::{Alignment, Corner, Margin, Rect},
+    style::{Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    widgets::{
+        Block, BorderType, Borders, Clear, List, ListItem, ListState, Paragraph, Sparkline, Wrap,
+    },
     Frame,
 };
 
-use crate::bot::{Alert, Bot};
+use crate::{
+    bot::{
+        self, order_book_imbalance, AlertKind, AlertLog, BotSnapshot, PriceStats, PriceTrigger,
+        Trade, TriggerTarget, WatchedSymbol,
+    },
+    connection::ConnectionState,
+    currency::Currency,
+    decimal::Decimal,
+    error::Error,
+    exchange::SymbolInfo,
+    format::Precision,
+    input::InputHandler,
+    layout::LayoutNode,
+    popup::{popup_block, Popup},
+    symbol::Symbol,
+    theme::Theme,
+};
 
+#[allow(clippy::upper_case_acronyms)]
 pub struct TUI {
     // Objects:
     live_price: LivePrice,
     alert_box: AlertBox,
     trigger_list: TriggerList,
     input_box: InputBox,
+    watchlist: Watchlist,
+    depth: Depth,
+    trade_tape: TradeTape,
+
+    theme: Theme,
+    layout: LayoutNode,
+    /// Panels toggled off at runtime.
+    hidden: Vec<Panel>,
+    /// Whether only the focused panel is shown.
+    maximized: bool,
+    terminal_size: Rect,
+
+    focus: Panel,
+    /// Key bindings listed in the help overlay while it is shown.
+    help: Option<Vec<(String, &'static str)>>,
+    popup: Option<Popup>,
+    log: Log,
 }
 
 impl TUI {
-    pub fn new() -> Self {
+    pub fn new(theme: Theme, layout: LayoutNode) -> Self {
         Self {
             live_price: LivePrice::default(),
             alert_box: AlertBox::default(),
             trigger_list: TriggerList::default(),
             input_box: InputBox::default(),
+            watchlist: Watchlist::default(),
+            depth: Depth::default(),
+            trade_tape: TradeTape::default(),
+
+            theme,
+            layout,
+            hidden: Vec::new(),
+            maximized: false,
+            terminal_size: Rect::default(),
+
+            focus: Panel::InputBox,
+            help: None,
+            popup: None,
+            log: Log::default(),
+        }
+    }
+
+    pub fn update(&mut self, bot: &BotSnapshot) {
+        let alerts = bot.alerts.clone();
+        if alerts.raised() > self.alert_box.alerts.raised() {
+            self.live_price.flash();
+        }
+        self.live_price.connection = bot.connection;
+        let main = bot.main();
+        self.live_price.info = main.info.clone();
+        self.live_price.conversion = bot.currency.clone().zip(main.rate);
+        self.live_price
+            .update((main.stats.clone(), bot::updated_at(main.age)));
+        let precisions = PricePrecisions::new(bot);
+        let rates = DisplayRates::new(bot);
+        self.alert_box.precisions = precisions.clone();
+        self.alert_box.rates = rates.clone();
+        self.alert_box.update(alerts);
+        self.trigger_list.precisions = precisions;
+        self.trigger_list.rates = rates;
+        self.trigger_list.update(&bot.triggers);
+        let rows = bot
+            .watchlist
+            .iter()
+            .enumerate()
+            .map(WatchlistRow::new)
+            .collect();
+        self.watchlist.update(rows);
+        let (prices, quantities) = (
+            Precision::price(main.info.as_ref()),
+            Precision::quantity(main.info.as_ref()),
+        );
+        (self.depth.prices, self.depth.quantities) = (prices, quantities);
+        self.depth
+            .update((bot.depth.clone(), bot::updated_at(bot.depth_age)));
+        (self.trade_tape.prices, self.trade_tape.quantities) = (prices, quantities);
+        self.trade_tape.large_notional = bot.large_trade_notional;
+        self.trade_tape
+            .update(bot.trades.iter().rev().copied().collect());
+    }
+
+    pub fn update_triggers(&mut self, triggers: &[PriceTrigger]) {
+        self.trigger_list.update(triggers);
+    }
+
+    pub fn update_input(&mut self, input: &InputHandler) {
+        self.input_box.update(input);
+    }
+
+    /// Shows the command results in the Input Box.
+    pub fn report(&mut self, mut lines: Vec<String>) {
+        let reports = &mut self.input_box.reports;
+        reports.append(&mut lines);
+        if reports.len() > InputBox::MAX_REPORTS {
+            reports.drain(..reports.len() - InputBox::MAX_REPORTS);
+        }
+    }
+
+    /// Shows the cursor in the Input Box and focuses it while editing.
+    pub fn set_editing(&mut self, editing: bool) {
+        self.input_box.editing = editing;
+        if editing {
+            self.set_focus(Panel::InputBox);
+        }
+    }
+
+    pub fn focus_next(&mut self) {
+        self.focus_offset(1);
+    }
+
+    pub fn focus_previous(&mut self) {
+        self.focus_offset(Panel::ORDER.len() - 1);
+    }
+
+    /// Moves the focus skipping the panels which aren't shown.
+    fn focus_offset(&mut self, offset: usize) {
+        let mut panel = self.focus.offset(offset);
+        while !self.is_shown(panel) && panel != self.focus {
+            panel = panel.offset(offset);
         }
+        self.set_focus(panel);
+    }
+
+    fn set_focus(&mut self, panel: Panel) {
+        self.focus = panel;
+        // Maximized and very short layouts depend on the focused panel
+        self.apply_layout();
     }
 
-    pub fn update(&mut self, bot: &Bot) {
-        self.live_price.update(bot.live_stats());
-        self.alert_box.update(bot.alert());
+    fn is_shown(&self, panel: Panel) -> bool {
+        !self.hidden.contains(&panel) && self.layout.contains(panel)
+    }
+
+    /// Shows or hides the `panel` moving the focus away from hidden panels.
+    pub fn set_panel_shown(
+        &mut self,
+        panel: Panel,
+        shown: bool,
+    ) -> std::result::Result<(), String> {
+        if !self.layout.contains(panel) {
+            return Err(format!("the '{panel}' panel isn't in the layout"));
+        }
+        if panel == Panel::InputBox && !shown {
+            return Err(format!("the '{panel}' panel can't be hidden"));
+        }
+
+        self.hidden.retain(|&p| p != panel);
+        if !shown {
+            self.hidden.push(panel);
+        }
+        if self.focus == panel && !shown {
+            self.focus_next();
+        } else {
+            self.apply_layout();
+        }
+        Ok(())
+    }
+
+    pub fn toggle_maximized(&mut self) {
+        self.maximized = !self.maximized;
+        self.apply_layout();
+    }
+
+    /// Scrolls the content of the focused panel.
+    pub fn scroll(&mut self, direction: ScrollDirection) {
+        self.scroll_panel(self.focus, direction);
+    }
+
+    fn scroll_panel(&mut self, panel: Panel, direction: ScrollDirection) {
+        match panel {
+            Panel::AlertBox => self.alert_box.scroll(direction),
+            Panel::TriggerList => self.trigger_list.scroll(direction),
+            Panel::Watchlist => self.watchlist.scroll(direction),
+            Panel::Trades => self.trade_tape.scroll(direction),
+            Panel::Log => self.log.scroll(direction),
+            Panel::InputBox | Panel::LivePrice | Panel::Depth => (),
+        }
+    }
+
+    /// Switches the watchlist to the next sort order.
+    pub fn cycle_watchlist_sort(&mut self) {
+        self.set_watchlist_sort(self.watchlist.sort.next());
+    }
+
+    pub fn set_watchlist_sort(&mut self, sort: WatchlistSort) {
+        self.watchlist.sort = sort;
+        self.watchlist.sort_rows();
+    }
+
+    /// Focuses the clicked panel, scrolls the panel under the mouse cursor and pans the
+    /// watchlist trends while they are dragged.
+    ///
+    /// Returns the clicked panel.
+    pub fn process_mouse(&mut self, event: MouseEvent) -> Option<Panel> {
+        // The drag continues even when the mouse leaves the panel
+        match event.kind {
+            MouseEventKind::Drag(MouseButton::Left) => self.watchlist.drag(event.column),
+            MouseEventKind::Up(MouseButton::Left) => self.watchlist.end_drag(),
+            _ => (),
+        }
+        let panel = self.panel_at(event.column, event.row)?;
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.set_focus(panel);
+                match panel {
+                    Panel::TriggerList => {
+                        let clicked = self.trigger_list.trigger_at(event.row);
+                        self.trigger_list.select(clicked);
+                    }
+                    Panel::Watchlist => self.watchlist.start_drag(event.column),
+                    _ => (),
+                }
+                return Some(panel);
+            }
+            MouseEventKind::ScrollUp => self.scroll_panel(panel, ScrollDirection::Up),
+            MouseEventKind::ScrollDown => self.scroll_panel(panel, ScrollDirection::Down),
+            _ => (),
+        }
+        None
+    }
+
+    fn panel_at(&self, column: u16, row: u16) -> Option<Panel> {
+        let point = Rect::new(column, row, 1, 1);
+        Panel::ORDER
+            .iter()
+            .copied()
+            .find(|&panel| self.panel_area(panel).intersects(point))
+    }
+
+    fn panel_area(&self, panel: Panel) -> Rect {
+        match panel {
+            Panel::AlertBox => self.alert_box.area,
+            Panel::TriggerList => self.trigger_list.area,
+            Panel::InputBox => self.input_box.area,
+            Panel::LivePrice => self.live_price.area,
+            Panel::Watchlist => self.watchlist.area,
+            Panel::Depth => self.depth.area,
+            Panel::Trades => self.trade_tape.area,
+            Panel::Log => self.log.area,
+        }
+    }
+
+    pub fn show_help(&mut self, bindings: Vec<(String, &'static str)>) {
+        self.help = Some(bindings);
+    }
+
+    pub fn hide_help(&mut self) {
+        self.help = None;
+    }
+
+    pub fn is_help_shown(&self) -> bool {
+        self.help.is_some()
+    }
+
+    /// Shows the `popup` on top of the layout replacing any other popup.
+    pub fn show_popup(&mut self, popup: Popup) {
+        self.popup = Some(popup);
+    }
+
+    pub fn close_popup(&mut self) {
+        self.popup = None;
+    }
+
+    pub fn popup_mut(&mut self) -> Option<&mut Popup> {
+        self.popup.as_mut()
+    }
+
+    /// Writes the error to the Log panel.
+    pub fn log_error(&mut self, error: &Error) {
+        self.log.push(error.to_string(), true);
+    }
+
+    /// Writes the informational `message` to the Log panel.
+    pub fn log_info(&mut self, message: String) {
+        self.log.push(message, false);
+    }
+
+    /// Returns the index of the trigger selected in the Trigger List.
+    pub fn selected_trigger(&self) -> Option<usize> {
+        self.trigger_list.selected
     }
 
     pub fn resize(&mut self, terminal_size: Rect) {
-        // Split the terminal into the main top part and bottom object.
-        let top_bottom = Layout::default()
-            .margin(1)
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Min(6), Constraint::Length(3)])
-            .split(terminal_size);
-
-        // BOTTOM LIVE_PRICE OBJECT
-        self.live_price.update_area(top_bottom[1]);
-
-        {
-            let left_right = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
-                .split(top_bottom[0]);
-
-            self.alert_box.update_area(left_right[0]);
-
-            {
-                let top_bottom = Layout::default()
-                    .direction(Direction::Vertical)
-                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-                    .split(left_right[1]);
-
-                self.trigger_list.update_area(top_bottom[0]);
-                self.input_box.update_area(top_bottom[1]);
+        self.terminal_size = terminal_size;
+        self.apply_layout();
+    }
+
+    /// Splits the terminal between the shown panels.
+    fn apply_layout(&mut self) {
+        let areas = if self.maximized {
+            let area = self.terminal_size.inner(&Margin {
+                vertical: 1,
+                horizontal: 1,
+            });
+            vec![(self.focus, area)]
+        } else {
+            let hidden = &self.hidden;
+            self.layout
+                .split(self.terminal_size, &|p| !hidden.contains(&p), self.focus)
+        };
+
+        // Panels which aren't a part of the layout are left without any area
+        for &panel in Panel::ORDER {
+            let area = areas
+                .iter()
+                .find(|(p, _)| *p == panel)
+                .map_or(Rect::default(), |(_, area)| *area);
+            match panel {
+                Panel::AlertBox => self.alert_box.update_area(area),
+                Panel::TriggerList => self.trigger_list.update_area(area),
+                Panel::InputBox => self.input_box.update_area(area),
+                Panel::LivePrice => self.live_price.update_area(area),
+                Panel::Watchlist => self.watchlist.update_area(area),
+                Panel::Depth => self.depth.update_area(area),
+                Panel::Trades => self.trade_tape.update_area(area),
+                Panel::Log => self.log.update_area(area),
             }
         }
     }
 
     pub fn render<B: Backend>(&self, frame: &mut Frame<B>) {
-        self.alert_box.render(frame);
-        self.input_box.render(frame);
-        self.live_price.render(frame);
-        self.trigger_list.render(frame);
+        let theme = &self.theme;
+        for &panel in Panel::ORDER {
+            if self.panel_area(panel).area() == 0 {
+                continue;
+            }
+            let focused = self.focus == panel;
+            match panel {
+                Panel::AlertBox => self.alert_box.render(frame, theme, focused),
+                Panel::TriggerList => self.trigger_list.render(frame, theme, focused),
+                Panel::InputBox => self.input_box.render(frame, theme, focused),
+                Panel::LivePrice => self.live_price.render(frame, theme, focused),
+                Panel::Watchlist => self.watchlist.render(frame, theme, focused),
+                Panel::Depth => self.depth.render(frame, theme, focused),
+                Panel::Trades => self.trade_tape.render(frame, theme, focused),
+                Panel::Log => self.log.render(frame, theme, focused),
+            }
+        }
+
+        if let Some(bindings) = &self.help {
+            render_help(frame, theme, bindings);
+        }
+        if let Some(popup) = &self.popup {
+            popup.render(frame, theme);
+        }
+    }
+}
+
+/// Panels which can be focused in the Control mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Panel {
+    AlertBox,
+    TriggerList,
+    InputBox,
+    LivePrice,
+    Watchlist,
+    Depth,
+    Trades,
+    Log,
+}
+
+impl Panel {
+    /// Order in which the panels are focused.
+    pub const ORDER: &[Panel] = &[
+        Panel::AlertBox,
+        Panel::Watchlist,
+        Panel::Trades,
+        Panel::Depth,
+        Panel::TriggerList,
+        Panel::InputBox,
+        Panel::Log,
+        Panel::LivePrice,
+    ];
+
+    fn offset(self, offset: usize) -> Panel {
+        let index = Self::ORDER.iter().position(|&p| p == self).unwrap();
+        Self::ORDER[(index + offset) % Self::ORDER.len()]
+    }
+}
+
+impl Display for Panel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Panel::AlertBox => "alerts",
+            Panel::TriggerList => "triggers",
+            Panel::InputBox => "input",
+            Panel::LivePrice => "live_stats",
+            Panel::Watchlist => "watchlist",
+            Panel::Depth => "depth",
+            Panel::Trades => "trades",
+            Panel::Log => "log",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Panel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Panel::ORDER
+            .iter()
+            .copied()
+            .find(|panel| panel.to_string() == s)
+            .ok_or_else(|| format!("unknown panel '{s}'"))
+    }
+}
+
+impl<'de> Deserialize<'de> for Panel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Panel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ScrollDirection {
+    Up,
+    Down,
+}
+
+/// Creates the bordered block every panel is drawn in.
+fn panel_block<'a>(title: &'a str, theme: &Theme, focused: bool) -> Block<'a> {
+    let border_type = if focused {
+        BorderType::Thick
+    } else {
+        BorderType::Plain
+    };
+    Block::default()
+        .borders(Borders::all())
+        .border_type(border_type)
+        .border_style(theme.border(focused))
+        .style(theme.text())
+        .title(title)
+}
+
+/// Formats the seconds since the Unix epoch as `HH:MM:SS` in UTC.
+fn format_clock(seconds: u64) -> String {
+    let seconds = seconds % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Returns a `width` x `height` rectangle centered inside the `area`.
+pub fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+fn render_help<B: Backend>(
+    frame: &mut Frame<B>,
+    theme: &Theme,
+    bindings: &[(String, &'static str)],
+) {
+    let key_width = bindings
+        .iter()
+        .map(|(keys, _)| keys.len())
+        .max()
+        .unwrap_or(0);
+    let text = bindings
+        .iter()
+        .map(|(keys, description)| Spans::from(format!("{keys:>key_width$}  {description}")))
+        .collect::<Vec<Spans>>();
+
+    let width = text.iter().map(|line| line.width()).max().unwrap_or(0) as u16 + 4;
+    let area = centered_rect(width, text.len() as u16 + 2, frame.size());
+    let paragraph = Paragraph::new(text).block(popup_block("Help", theme));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(paragraph, area);
+}
+
+/// Precisions of the prices of the watched symbols used to show the trigger levels.
+#[derive(Default, Clone)]
+struct PricePrecisions {
+    main: Precision,
+    symbols: HashMap<Symbol, Precision>,
+}
+
+impl PricePrecisions {
+    fn new(bot: &BotSnapshot) -> Self {
+        Self {
+            main: Precision::price(bot.main().info.as_ref()),
+            symbols: bot
+                .watchlist
+                .iter()
+                .map(|watched| {
+                    (
+                        watched.symbol.clone(),
+                        Precision::price(watched.info.as_ref()),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the precision of the `symbol` of a trigger, `None` being the main symbol.
+    fn get(&self, symbol: Option<&Symbol>) -> Precision {
+        match symbol {
+            Some(symbol) => self.symbols.get(symbol).copied().unwrap_or_default(),
+            None => self.main,
+        }
+    }
+}
+
+/// Rates of the watched symbols used to show the price levels in the display currency.
+#[derive(Default, Clone)]
+struct DisplayRates {
+    currency: Option<Currency>,
+    main: Option<Decimal>,
+    symbols: HashMap<Symbol, Decimal>,
+}
+
+impl DisplayRates {
+    fn new(bot: &BotSnapshot) -> Self {
+        Self {
+            currency: bot.currency.clone(),
+            main: bot.main().rate,
+            symbols: bot
+                .watchlist
+                .iter()
+                .filter_map(|watched| Some((watched.symbol.clone(), watched.rate?)))
+                .collect(),
+        }
+    }
+
+    /// Formats the `price` of the `symbol`, `None` being the main symbol, in the
+    /// display currency like ` (≈ 1,105.23 EUR)` or returns an empty string if the
+    /// price can't be converted.
+    fn convert(&self, symbol: Option<&Symbol>, price: Decimal) -> String {
+        let rate = match symbol {
+            Some(symbol) => self.symbols.get(symbol).copied(),
+            None => self.main,
+        };
+        match (
+            &self.currency,
+            rate.and_then(|rate| price.checked_mul(rate)),
+        ) {
+            (Some(currency), Some(price)) => {
+                let price = currency.precision().format(price);
+                format!(" (≈ {price} {currency})")
+            }
+            _ => String::new(),
+        }
     }
 }
 
 #[derive(Default)]
 struct AlertBox {
     area: Rect,
-    alerts: Vec<Alert>,
+    alerts: AlertLog,
+    precisions: PricePrecisions,
+    rates: DisplayRates,
+
+    /// Number of lines scrolled up from the latest alert.
+    scroll: usize,
 }
 
 impl AlertBox {
-    const POINTER: &str = "-> ";
+    const LINES_PER_ALERT: usize = 2;
+    /// For how long the alerts are highlighted after being fired.
+    const NEW_ALERT_DURATION: Duration = Duration::from_secs(10);
+
+    fn max_scroll(&self) -> usize {
+        let height = self.area.height.saturating_sub(2) as usize;
+        (self.alerts.len() * Self::LINES_PER_ALERT).saturating_sub(height)
+    }
 }
 
 impl Object for AlertBox {
     fn update_area(&mut self, new_area: Rect) {
-        self.area = new_area
+        self.area = new_area;
+        self.scroll = self.scroll.min(self.max_scroll());
     }
 
-    fn render<B: Backend>(&self, frame: &mut Frame<B>) {
+    fn render<B: Backend>(&self, frame: &mut Frame<B>, theme: &Theme, focused: bool) {
         let mut text = Vec::new();
 
-        for alert in self.alerts.iter() {
-            text.push(Spans::from(vec![
-                Span::raw("!!! Alert at"),
-                Span::raw(alert.price.0.to_string()),
-                Span::raw("$ !!!"),
-            ]));
+        let now = SystemTime::now();
+        for (_, alert) in self.alerts.iter() {
+            let age = now.duration_since(alert.time).unwrap_or_default();
+            let style = if age < Self::NEW_ALERT_DURATION {
+                Style::default()
+                    .fg(theme.new_alert)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.alert)
+            };
+            let precision = self.precisions.get(alert.symbol.as_ref());
+            // Only the levels in the quote asset are converted
+            let converted = match alert.kind {
+                AlertKind::Trigger {
+                    price,
+                    target: TriggerTarget::Price,
+                } if alert.currency.is_none() => self.rates.convert(alert.symbol.as_ref(), price.0),
+                _ => String::new(),
+            };
+            let alert = alert.describe(Some(precision)) + &converted;
+            text.push(Spans::from(Span::styled(format!("!!! {alert} !!!"), style)));
             text.push(Spans::default());
         }
 
+        let offset = self.max_scroll() - self.scroll;
         let paragraph = Paragraph::new(text)
-            .block(
-                Block::default()
-                    .borders(Borders::all())
-                    .title("Price Alerts"),
-            )
+            .block(panel_block("Price Alerts", theme, focused))
             .wrap(Wrap { trim: true })
-            .alignment(Alignment::Center);
+            .alignment(Alignment::Center)
+            .scroll((offset as u16, 0));
 
         frame.render_widget(paragraph, self.area)
     }
 }
 
-impl DynamicObject<Vec<Alert>> for AlertBox {
-    fn update(&mut self, mut data: Vec<Alert>) {
-        self.alerts.append(&mut data);
+impl DynamicObject<AlertLog> for AlertBox {
+    fn update(&mut self, data: AlertLog) {
+        // Keep the same alerts in view if the user has scrolled up
+        if self.scroll > 0 {
+            let new_alerts = data.raised().saturating_sub(self.alerts.raised());
+            self.scroll += new_alerts * Self::LINES_PER_ALERT;
+        }
+        self.alerts = data;
+        self.scroll = self.scroll.min(self.max_scroll());
+    }
+}
+
+impl ScrollableObject for AlertBox {
+    fn scroll(&mut self, direction: ScrollDirection) {
+        self.scroll = match direction {
+            ScrollDirection::Up => (self.scroll + Self::LINES_PER_ALERT).min(self.max_scroll()),
+            ScrollDirection::Down => self.scroll.saturating_sub(Self::LINES_PER_ALERT),
+        };
     }
 }
 
 #[derive(Default)]
 struct TriggerList {
     area: Rect,
+    triggers: Vec<PriceTrigger>,
+    selected: Option<usize>,
+    precisions: PricePrecisions,
+    rates: DisplayRates,
+
+    /// Index of the first visible trigger.
+    offset: usize,
+}
+
+impl TriggerList {
+    fn visible_items(&self) -> usize {
+        self.area.height.saturating_sub(2) as usize
+    }
+
+    fn select(&mut self, index: Option<usize>) {
+        self.selected = index.filter(|&i| i < self.triggers.len());
+        self.scroll_to_selected();
+    }
+
+    /// Moves the offset the least amount so the selected trigger is visible.
+    fn scroll_to_selected(&mut self) {
+        let visible = self.visible_items().max(1);
+        if let Some(selected) = self.selected {
+            if selected < self.offset {
+                self.offset = selected;
+            } else if selected >= self.offset + visible {
+                self.offset = selected + 1 - visible;
+            }
+        }
+        self.offset = self.offset.min(self.triggers.len().saturating_sub(visible));
+    }
+
+    /// Returns the index of the trigger drawn at the terminal `row`.
+    fn trigger_at(&self, row: u16) -> Option<usize> {
+        let inner = self.area.inner(&Margin {
+            vertical: 1,
+            horizontal: 1,
+        });
+        if row < inner.top() || row >= inner.bottom() {
+            return None;
+        }
+        // Triggers are listed from the bottom corner
+        let index = self.offset + (inner.bottom() - 1 - row) as usize;
+        (index < self.triggers.len()).then_some(index)
+    }
 }
 
 impl Object for TriggerList {
     fn update_area(&mut self, new_area: Rect) {
-        self.area = new_area
+        self.area = new_area;
+        self.scroll_to_selected();
     }
 
-    fn render<B: Backend>(&self, frame: &mut Frame<B>) {
-        let items = [
-            ListItem::new("Price Trigger at 1200"),
-            ListItem::new("Mama ti"),
-        ];
+    fn render<B: Backend>(&self, frame: &mut Frame<B>, theme: &Theme, focused: bool) {
+        let items = self
+            .triggers
+            .iter()
+            .enumerate()
+            .skip(self.offset)
+            .map(|(i, trigger)| {
+                let precision = self.precisions.get(trigger.symbol.as_ref());
+                let converted = match (trigger.target, &trigger.currency) {
+                    (TriggerTarget::Price, None) => {
+                        self.rates.convert(trigger.symbol.as_ref(), trigger.price.0)
+                    }
+                    _ => String::new(),
+                };
+                let item = ListItem::new(format!(
+                    "{}. {}{converted}",
+                    i + 1,
+                    trigger.describe(Some(precision))
+                ));
+                if trigger.enabled {
+                    item
+                } else {
+                    item.style(
+                        Style::default()
+                            .fg(theme.disabled)
+                            .add_modifier(Modifier::DIM),
+                    )
+                }
+            })
+            .collect::<Vec<ListItem>>();
 
         let list = List::new(items)
-            .block(
-                Block::default()
-                    .title("Price Triggers")
-                    .borders(Borders::all()),
-            )
+            .block(panel_block("Price Triggers", theme, focused))
+            .highlight_style(Style::default().fg(theme.highlight))
             .start_corner(Corner::BottomLeft)
             .repeat_highlight_symbol(true)
             .highlight_symbol(">>");
 
         let mut state = ListState::default();
-        state.select(Some(0));
+        state.select(self.selected.map(|selected| selected - self.offset));
 
         frame.render_stateful_widget(list, self.area, &mut state);
     }
 }
 
+impl DynamicObject<&[PriceTrigger]> for TriggerList {
+    fn update(&mut self, data: &[PriceTrigger]) {
+        self.triggers = data.to_vec();
+        self.select(self.selected);
+    }
+}
+
+impl ScrollableObject for TriggerList {
+    fn scroll(&mut self, direction: ScrollDirection) {
+        if self.triggers.is_empty() {
+            return;
+        }
+        // The list starts from the bottom so scrolling up selects the following triggers
+        let selected = match (direction, self.selected) {
+            (_, None) => 0,
+            (ScrollDirection::Up, Some(i)) => (i + 1).min(self.triggers.len() - 1),
+            (ScrollDirection::Down, Some(i)) => i.saturating_sub(1),
+        };
+        self.select(Some(selected));
+    }
+}
+
 #[derive(Default)]
 struct InputBox {
     area: Rect,
+    input: String,
+    cursor: usize,
+    editing: bool,
+
+    /// Results of the executed commands.
+    reports: Vec<String>,
+}
+
+impl InputBox {
+    const PROMPT: &str = "> ";
+    const MAX_REPORTS: usize = 100;
 }
 
 impl Object for InputBox {
@@ -163,25 +841,251 @@ impl Object for InputBox {
         self.area = new_area
     }
 
-    fn render<B: Backend>(&self, frame: &mut Frame<B>) {
-        let temp = Block::default().title("Input Box").borders(Borders::all());
+    fn render<B: Backend>(&self, frame: &mut Frame<B>, theme: &Theme, focused: bool) {
+        let block = panel_block("Input Box", theme, focused);
+        let inner = block.inner(self.area);
+        if inner.height == 0 || inner.width == 0 {
+            frame.render_widget(block, self.area);
+            return;
+        }
+
+        // Only the latest reports which fit above the prompt line are shown
+        let shown = self.reports.len().min(inner.height as usize - 1);
+        let mut text = self.reports[self.reports.len() - shown..]
+            .iter()
+            .map(|report| Spans::from(report.as_str()))
+            .collect::<Vec<Spans>>();
+        text.push(Spans::from(vec![
+            Span::raw(Self::PROMPT),
+            Span::raw(&self.input),
+        ]));
+
+        let paragraph = Paragraph::new(text).block(block);
+        frame.render_widget(paragraph, self.area);
 
-        frame.render_widget(temp, self.area);
+        if !self.editing {
+            return;
+        }
+        let cursor_x = inner.x + (Self::PROMPT.len() + self.cursor) as u16;
+        frame.set_cursor(cursor_x.min(inner.right() - 1), inner.y + shown as u16);
+    }
+}
+
+impl DynamicObject<&InputHandler> for InputBox {
+    fn update(&mut self, data: &InputHandler) {
+        self.input = data.current_input().to_owned();
+        self.cursor = data.get_cursor_position();
     }
 }
 
 struct LivePrice {
     area: Rect,
     stats: Arc<PriceStats>,
+    /// When the stats were last read from the market.
+    updated: Option<Instant>,
+    /// State of the connection to Binance shown in the title.
+    connection: ConnectionState,
+    /// Trading rules of the symbol if the exchange info is known.
+    info: Option<SymbolInfo>,
+    /// Display currency with the rate of the quote asset once it is known.
+    conversion: Option<(Currency, Decimal)>,
+
+    /// Direction of the last price change.
+    tick: Ordering,
+    /// When the border started flashing because of a fired trigger.
+    flash_start: Option<Instant>,
 }
 
 impl LivePrice {
-    const CONSTS: &[&'static str] = &["Symbol: ", "Last Price: ", "24h% Change: ", "%"];
-    const SECTION_LENGTHS: &[u16] = &[
-        Self::CONSTS[0].len() as u16,
-        Self::CONSTS[1].len() as u16,
-        (Self::CONSTS[2].len() + Self::CONSTS[3].len()) as u16,
-    ];
+    const FLASH_DURATION: Duration = Duration::from_secs(4);
+    const SEPARATOR: &str = " | ";
+
+    fn flash(&mut self) {
+        self.flash_start = Some(Instant::now());
+    }
+
+    /// The border alternates between the flash and normal colour every second.
+    fn is_flash_on(&self) -> bool {
+        match self.flash_start.map(|start| start.elapsed()) {
+            Some(elapsed) => elapsed < Self::FLASH_DURATION && elapsed.as_secs() % 2 == 0,
+            None => false,
+        }
+    }
+
+    fn change_style(theme: &Theme, change: Ordering) -> Style {
+        match change {
+            Ordering::Greater => Style::default().fg(theme.price_up),
+            Ordering::Less => Style::default().fg(theme.price_down),
+            Ordering::Equal => theme.text(),
+        }
+    }
+
+    /// All stats fields in the order they are displayed.
+    fn fields(&self, theme: &Theme) -> Vec<StatsField> {
+        let stats = &self.stats;
+        let arrow = match self.tick {
+            Ordering::Greater => "▲ ",
+            Ordering::Less => "▼ ",
+            Ordering::Equal => "• ",
+        };
+        let change = stats.price_change_percent;
+        let change_style = Self::change_style(theme, change.cmp(&Decimal::ZERO));
+        let price = |price: Decimal| Precision::price(self.info.as_ref()).format(price);
+        let volume = Precision::quantity(self.info.as_ref()).format(stats.volume);
+
+        let mut fields = vec![
+            StatsField::new(0, "Symbol: ", stats.symbol.clone(), theme.text()),
+            StatsField::new(
+                1,
+                "Last: ",
+                format!("{arrow}{}", price(stats.last_price)),
+                Self::change_style(theme, self.tick),
+            ),
+        ];
+        let converted = self
+            .conversion
+            .as_ref()
+            .and_then(|(currency, rate)| Some((currency, stats.last_price.checked_mul(*rate)?)));
+        if let Some((currency, converted)) = converted {
+            let converted = currency.precision().format(converted);
+            fields.push(StatsField::new(
+                2,
+                "≈ ",
+                format!("{converted} {currency}"),
+                theme.text(),
+            ));
+        }
+        fields.extend([
+            StatsField::new(
+                3,
+                "24h: ",
+                format!(
+                    "{} ({}%)",
+                    price(stats.price_change),
+                    Precision::decimals(2).format(change)
+                ),
+                change_style,
+            ),
+            StatsField::new(
+                4,
+                "Bid/Ask: ",
+                format!("{}/{}", price(stats.bid_price), price(stats.ask_price)),
+                theme.text(),
+            ),
+            StatsField::new(
+                5,
+                "High/Low: ",
+                format!("{}/{}", price(stats.high_price), price(stats.low_price)),
+                theme.text(),
+            ),
+            StatsField::new(8, "Open: ", price(stats.open_price), theme.text()),
+            StatsField::new(6, "Volume: ", volume, theme.text()),
+            StatsField::new(7, "Avg: ", price(stats.weighted_avg_price), theme.text()),
+            StatsField::new(
+                9,
+                "Trades: ",
+                Precision::decimals(0).format(stats.count as f64),
+                theme.text(),
+            ),
+        ]);
+        if let Some(info) = &self.info {
+            fields.push(StatsField::new(10, "Pair: ", info.pair(), theme.text()));
+            let step = |step: &Option<String>| step.clone().unwrap_or_else(|| "-".to_owned());
+            fields.push(StatsField::new(
+                11,
+                "Tick: ",
+                step(&info.tick_size),
+                theme.text(),
+            ));
+            fields.push(StatsField::new(
+                12,
+                "Lot: ",
+                step(&info.lot_size),
+                theme.text(),
+            ));
+        }
+        fields
+    }
+
+    /// Picks the fields with the highest priority which fit into `rows` lines of
+    /// `width` cells and splits them into lines.
+    fn fit_fields(fields: Vec<StatsField>, width: usize, rows: usize) -> Vec<Vec<StatsField>> {
+        let mut by_priority = fields.iter().map(|f| f.priority).collect::<Vec<u8>>();
+        by_priority.sort_unstable();
+
+        let mut included = Vec::new();
+        for priority in by_priority {
+            included.push(priority);
+            let lines = Self::wrap_fields(&fields, &included, width);
+            if lines.len() > rows || lines.iter().any(|line| line.is_empty()) {
+                included.pop();
+                break;
+            }
+        }
+
+        let counts = Self::wrap_fields(&fields, &included, width)
+            .iter()
+            .map(Vec::len)
+            .collect::<Vec<usize>>();
+        let mut fields = fields
+            .into_iter()
+            .filter(|f| included.contains(&f.priority));
+        counts
+            .into_iter()
+            .map(|count| fields.by_ref().take(count).collect())
+            .collect()
+    }
+
+    /// Splits the `included` fields into lines no wider than `width`.
+    ///
+    /// A line is left empty if a single field doesn't fit into it.
+    fn wrap_fields<'a>(
+        fields: &'a [StatsField],
+        included: &[u8],
+        width: usize,
+    ) -> Vec<Vec<&'a StatsField>> {
+        let mut lines: Vec<Vec<&StatsField>> = vec![Vec::new()];
+        let mut line_width = 0;
+        for field in fields.iter().filter(|f| included.contains(&f.priority)) {
+            let line = lines.last_mut().unwrap();
+            if line.is_empty() {
+                if field.width() > width {
+                    return vec![Vec::new()];
+                }
+                line.push(field);
+                line_width = field.width();
+            } else if line_width + Self::SEPARATOR.len() + field.width() <= width {
+                line.push(field);
+                line_width += Self::SEPARATOR.len() + field.width();
+            } else {
+                lines.push(vec![field]);
+                line_width = field.width();
+            }
+        }
+        lines
+    }
+
+    fn title(&self, theme: &Theme) -> Spans<'static> {
+        let updated = match self.updated.map(|updated| updated.elapsed()) {
+            Some(age) if age > ConnectionState::STALE_AFTER => {
+                format!(" - STALE, updated {}s ago", age.as_secs())
+            }
+            Some(age) => format!(" - updated {}s ago", age.as_secs()),
+            None => " - no data".to_owned(),
+        };
+        let connection_style = match self.connection {
+            ConnectionState::Connected => Style::default().fg(theme.price_up),
+            ConnectionState::Degraded => Style::default().fg(theme.alert),
+            ConnectionState::Down => Style::default()
+                .fg(theme.price_down)
+                .add_modifier(Modifier::BOLD),
+        };
+        Spans::from(vec![
+            Span::raw("Live Stats - "),
+            Span::styled(self.connection.to_string(), connection_style),
+            Span::raw(updated),
+        ])
+    }
 }
 
 impl Object for LivePrice {
@@ -189,55 +1093,51 @@ impl Object for LivePrice {
         self.area = new_area
     }
 
-    // TODO Maybe remove to_string() and add references
-    fn render<B: Backend>(&self, frame: &mut Frame<B>) {
-        //let area_width = self.area.width - 2;
-        //let section_coverage = vec![
-        //    Self::SECTION_LENGTHS[0] + self.stats.symbol.len() as u16,
-        //    Self::SECTION_LENGTHS[1] + self.stats.last_price.to_string().len() as u16,
-        //    Self::SECTION_LENGTHS[2] + self.stats.price_change_percent.len() as u16,
-        //];
-        //println!("{:?}", section_coverage);
-
-        let sections = Layout::default()
-            .margin(1)
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Percentage(30),
-                Constraint::Percentage(30),
-                Constraint::Percentage(30),
-            ])
-            .split(self.area);
-
-        let text1 = vec![Spans::from(vec![
-            Span::raw(Self::CONSTS[0]),
-            Span::raw(&self.stats.symbol),
-        ])];
-        let text2 = vec![Spans::from(vec![
-            Span::raw(Self::CONSTS[1]),
-            Span::raw(self.stats.last_price.to_string()),
-        ])];
-        let text3 = vec![Spans::from(vec![
-            Span::raw(Self::CONSTS[2]),
-            Span::raw(&self.stats.price_change_percent),
-            Span::raw(Self::CONSTS[3]),
-        ])];
-
-        let section1 = Paragraph::new(text1).alignment(Alignment::Center);
-        let section2 = Paragraph::new(text2).alignment(Alignment::Center);
-        let section3 = Paragraph::new(text3).alignment(Alignment::Center);
-        let block = Block::default().borders(Borders::all()).title("Live Stats");
-
+    fn render<B: Backend>(&self, frame: &mut Frame<B>, theme: &Theme, focused: bool) {
+        let mut block = panel_block("", theme, focused).title(self.title(theme));
+        if self.is_flash_on() {
+            block = block.border_style(
+                Style::default()
+                    .fg(theme.flash)
+                    .add_modifier(Modifier::BOLD),
+            );
+        }
+        let inner = block.inner(self.area);
         frame.render_widget(block, self.area);
-        frame.render_widget(section1, sections[0]);
-        frame.render_widget(section2, sections[1]);
-        frame.render_widget(section3, sections[2]);
+
+        let rows = Self::fit_fields(
+            self.fields(theme),
+            inner.width as usize,
+            inner.height as usize,
+        );
+        let text = rows
+            .into_iter()
+            .map(|row| {
+                let mut spans = Vec::new();
+                for (i, field) in row.into_iter().enumerate() {
+                    if i > 0 {
+                        spans.push(Span::raw(Self::SEPARATOR));
+                    }
+                    spans.push(Span::raw(field.label));
+                    spans.push(Span::styled(field.value, field.style));
+                }
+                Spans::from(spans)
+            })
+            .collect::<Vec<Spans>>();
+
+        let paragraph = Paragraph::new(text).alignment(Alignment::Center);
+        frame.render_widget(paragraph, inner);
     }
 }
 
-impl DynamicObject<Arc<PriceStats>> for LivePrice {
-    fn update(&mut self, data: Arc<PriceStats>) {
+impl DynamicObject<(Arc<PriceStats>, Option<Instant>)> for LivePrice {
+    fn update(&mut self, (data, updated): (Arc<PriceStats>, Option<Instant>)) {
+        // Only compare prices of the same symbol and keep the direction if nothing changed
+        if data.symbol == self.stats.symbol && data.last_price != self.stats.last_price {
+            self.tick = data.last_price.cmp(&self.stats.last_price);
+        }
         self.stats = data;
+        self.updated = updated;
     }
 }
 
@@ -246,8 +1146,716 @@ impl Default for LivePrice {
         Self {
             area: Default::default(),
             stats: Arc::new(crate::bot::DEFAULT_PRICE_STATS),
+            updated: None,
+            connection: ConnectionState::default(),
+            info: None,
+            conversion: None,
+            tick: Ordering::Equal,
+            flash_start: None,
+        }
+    }
+}
+
+/// A single labeled value shown in the Live Stats panel.
+#[derive(Debug)]
+struct StatsField {
+    /// Fields with lower numbers are kept when there isn't enough space.
+    priority: u8,
+    label: &'static str,
+    value: String,
+    style: Style,
+}
+
+impl StatsField {
+    fn new(priority: u8, label: &'static str, value: String, style: Style) -> Self {
+        Self {
+            priority,
+            label,
+            value,
+            style,
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.label.chars().count() + self.value.chars().count()
+    }
+}
+
+#[derive(Default)]
+struct Watchlist {
+    area: Rect,
+    rows: Vec<WatchlistRow>,
+    sort: WatchlistSort,
+
+    /// Index of the first visible row.
+    offset: usize,
+    /// Number of the latest prices hidden by panning the trends into the past.
+    pan: usize,
+    /// Column of the mouse while the trends are dragged.
+    drag_column: Option<u16>,
+}
+
+impl Watchlist {
+    const SYMBOL_WIDTH: usize = 10;
+    const PRICE_WIDTH: usize = 14;
+    const CHANGE_WIDTH: usize = 9;
+    /// Sparkline values are scaled between 0 and this value.
+    const SPARKLINE_MAX: u64 = 100;
+
+    fn visible_rows(&self) -> usize {
+        // Without the borders and the header
+        self.area.height.saturating_sub(3) as usize
+    }
+
+    fn sort_rows(&mut self) {
+        match self.sort {
+            WatchlistSort::None => self.rows.sort_by_key(|row| row.order),
+            WatchlistSort::Change => self.rows.sort_by_key(|row| Reverse(row.change())),
+            WatchlistSort::Volume => self.rows.sort_by_key(|row| Reverse(row.quote_volume())),
         }
     }
+
+    fn clamp_offset(&mut self) {
+        let max_offset = self.rows.len().saturating_sub(self.visible_rows());
+        self.offset = self.offset.min(max_offset);
+    }
+
+    /// Returns the first column and the width of the trends.
+    fn sparkline_columns(&self) -> (u16, u16) {
+        let columns = Self::SYMBOL_WIDTH + Self::PRICE_WIDTH + Self::CHANGE_WIDTH;
+        let x = self.area.x + 1 + columns as u16 + 2;
+        // Without the right border
+        let width = self.area.right().saturating_sub(1).saturating_sub(x);
+        (x, width)
+    }
+
+    /// Starts dragging the trends if the `column` is on them.
+    fn start_drag(&mut self, column: u16) {
+        let (x, width) = self.sparkline_columns();
+        self.drag_column = (column >= x && column < x + width).then_some(column);
+    }
+
+    /// Pans the trends by the columns the mouse moved, dragging right shows older prices.
+    fn drag(&mut self, column: u16) {
+        let Some(last) = self.drag_column.replace(column) else {
+            return;
+        };
+        self.pan = match column >= last {
+            true => self.pan + (column - last) as usize,
+            false => self.pan.saturating_sub((last - column) as usize),
+        };
+        self.clamp_pan();
+    }
+
+    fn end_drag(&mut self) {
+        self.drag_column = None;
+    }
+
+    /// Keeps at least a full trend of the longest history visible.
+    fn clamp_pan(&mut self) {
+        let width = self.sparkline_columns().1 as usize;
+        let longest = self.rows.iter().map(|row| row.history.len()).max();
+        let max_pan = longest.unwrap_or_default().saturating_sub(width);
+        self.pan = self.pan.min(max_pan);
+    }
+}
+
+impl Object for Watchlist {
+    fn update_area(&mut self, new_area: Rect) {
+        self.area = new_area;
+        self.clamp_offset();
+        self.clamp_pan();
+    }
+
+    fn render<B: Backend>(&self, frame: &mut Frame<B>, theme: &Theme, focused: bool) {
+        let mut title = match self.sort {
+            WatchlistSort::None => "Watchlist".to_owned(),
+            sort => format!("Watchlist - by {sort}"),
+        };
+        if self.pan > 0 {
+            title += &format!(" - {} prices back", self.pan);
+        }
+        let block = panel_block(&title, theme, focused);
+        let inner = block.inner(self.area);
+        frame.render_widget(block, self.area);
+        if inner.height == 0 {
+            return;
+        }
+
+        let header = format!(
+            "{:<sw$}{:>pw$}{:>cw$}  Trend",
+            "Symbol",
+            "Last",
+            "24h%",
+            sw = Self::SYMBOL_WIDTH,
+            pw = Self::PRICE_WIDTH,
+            cw = Self::CHANGE_WIDTH,
+        );
+        let header_area = Rect::new(inner.x, inner.y, inner.width, 1);
+        let header_style = theme.text().add_modifier(Modifier::BOLD);
+        frame.render_widget(
+            Paragraph::new(Span::styled(header, header_style)),
+            header_area,
+        );
+
+        let (sparkline_x, sparkline_width) = self.sparkline_columns();
+        let visible = self.rows.iter().skip(self.offset).take(self.visible_rows());
+        for (i, row) in visible.enumerate() {
+            let y = inner.y + 1 + i as u16;
+            let change = row.change();
+            let change_style = LivePrice::change_style(theme, change.cmp(&Decimal::ZERO));
+            let text = Spans::from(vec![
+                Span::raw(format!("{:<w$}", row.symbol, w = Self::SYMBOL_WIDTH)),
+                Span::raw(format!(
+                    "{:>w$}",
+                    row.precision.format(row.stats.last_price),
+                    w = Self::PRICE_WIDTH
+                )),
+                Span::styled(
+                    format!(
+                        "{:>w$}",
+                        Precision::decimals(2).format(change),
+                        w = Self::CHANGE_WIDTH
+                    ),
+                    change_style,
+                ),
+            ]);
+            frame.render_widget(Paragraph::new(text), Rect::new(inner.x, y, inner.width, 1));
+
+            if sparkline_width > 0 {
+                let data = row.sparkline(sparkline_width as usize, self.pan);
+                let sparkline = Sparkline::default()
+                    .data(&data)
+                    .max(Self::SPARKLINE_MAX)
+                    .style(change_style);
+                frame.render_widget(sparkline, Rect::new(sparkline_x, y, sparkline_width, 1));
+            }
+        }
+    }
+}
+
+impl DynamicObject<Vec<WatchlistRow>> for Watchlist {
+    fn update(&mut self, data: Vec<WatchlistRow>) {
+        self.rows = data;
+        self.sort_rows();
+        self.clamp_offset();
+        self.clamp_pan();
+    }
+}
+
+impl ScrollableObject for Watchlist {
+    fn scroll(&mut self, direction: ScrollDirection) {
+        self.offset = match direction {
+            ScrollDirection::Up => self.offset.saturating_sub(1),
+            ScrollDirection::Down => self.offset + 1,
+        };
+        self.clamp_offset();
+    }
+}
+
+struct WatchlistRow {
+    /// Position of the symbol in the watchlist.
+    order: usize,
+    symbol: Symbol,
+    stats: Arc<PriceStats>,
+    history: Vec<f64>,
+    /// Precision of the prices of the symbol.
+    precision: Precision,
+}
+
+impl WatchlistRow {
+    fn new((order, watched): (usize, &WatchedSymbol)) -> Self {
+        Self {
+            order,
+            symbol: watched.symbol.clone(),
+            stats: watched.stats.clone(),
+            history: watched.history.clone(),
+            precision: Precision::price(watched.info.as_ref()),
+        }
+    }
+
+    fn change(&self) -> Decimal {
+        self.stats.price_change_percent
+    }
+
+    /// The 24h volume in the quote asset so it can be compared between different symbols.
+    fn quote_volume(&self) -> Decimal {
+        self.stats.quote_volume
+    }
+
+    /// Returns `width` prices ending `pan` prices before the latest one scaled between
+    /// their minimum and maximum.
+    fn sparkline(&self, width: usize, pan: usize) -> Vec<u64> {
+        let end = self.history.len().saturating_sub(pan);
+        let prices = &self.history[end.saturating_sub(width)..end];
+        let min = prices.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = prices.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let range = max - min;
+        prices
+            .iter()
+            .map(|price| {
+                if range > 0.0 {
+                    ((price - min) / range * Watchlist::SPARKLINE_MAX as f64) as u64
+                } else {
+                    Watchlist::SPARKLINE_MAX / 2
+                }
+            })
+            .collect()
+    }
+}
+
+/// Order of the watchlist rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WatchlistSort {
+    /// In the order the symbols were added.
+    #[default]
+    None,
+    /// By the 24h price change, highest first.
+    Change,
+    /// By the 24h volume, highest first.
+    Volume,
+}
+
+impl WatchlistSort {
+    const ALL: &[WatchlistSort] = &[
+        WatchlistSort::None,
+        WatchlistSort::Change,
+        WatchlistSort::Volume,
+    ];
+
+    fn next(self) -> WatchlistSort {
+        let index = Self::ALL.iter().position(|&s| s == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl Display for WatchlistSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            WatchlistSort::None => "none",
+            WatchlistSort::Change => "change",
+            WatchlistSort::Volume => "volume",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for WatchlistSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|sort| sort.to_string() == s)
+            .ok_or_else(|| format!("unknown sort order '{s}'"))
+    }
+}
+
+/// Top of the order book of the main symbol with bids on the left and asks on the right.
+struct Depth {
+    area: Rect,
+    book: Arc<OrderBook>,
+    /// When the order book was last read from the market.
+    updated: Option<Instant>,
+    prices: Precision,
+    quantities: Precision,
+}
+
+impl Depth {
+    fn title(&self) -> String {
+        let (bid, ask) = match (self.book.bids.first(), self.book.asks.first()) {
+            (Some(bid), Some(ask)) => (bid.price, ask.price),
+            _ => return "Order Book - no data".to_owned(),
+        };
+        let spread = ask - bid;
+        let mut title = format!(
+            "Order Book - spread {} ({:.3}%)",
+            self.prices.format(spread),
+            spread / ask * 100.0
+        );
+        if let Some(imbalance) = order_book_imbalance(&self.book) {
+            title += &format!(" | imbalance {:+.1}%", imbalance.to_f64());
+        }
+        let age = self.updated.map(|updated| updated.elapsed());
+        if age.is_some_and(|age| age > ConnectionState::STALE_AFTER) {
+            title += " - STALE";
+        }
+        title
+    }
+
+    /// Splits the `text` into a bar covering `bar` cells from one side and the rest.
+    fn bar_spans(text: String, bar: usize, from_right: bool, style: Style) -> Vec<Span<'static>> {
+        let width = text.chars().count();
+        let split = if from_right {
+            width.saturating_sub(bar)
+        } else {
+            bar.min(width)
+        };
+        let (left, right): (String, String) = (
+            text.chars().take(split).collect(),
+            text.chars().skip(split).collect(),
+        );
+        let bar_style = style.add_modifier(Modifier::REVERSED);
+        if from_right {
+            vec![Span::raw(left), Span::styled(right, bar_style)]
+        } else {
+            vec![Span::styled(left, bar_style), Span::raw(right)]
+        }
+    }
+}
+
+impl Object for Depth {
+    fn update_area(&mut self, new_area: Rect) {
+        self.area = new_area
+    }
+
+    fn render<B: Backend>(&self, frame: &mut Frame<B>, theme: &Theme, focused: bool) {
+        let title = self.title();
+        let block = panel_block(&title, theme, focused);
+        let inner = block.inner(self.area);
+        frame.render_widget(block, self.area);
+        if inner.height == 0 {
+            return;
+        }
+
+        let half = inner.width as usize / 2;
+        let price_width = half / 2;
+        let size_width = half - price_width;
+        let side = |left: String, right: String, left_width, right_width| {
+            let text = format!("{left:<left_width$}{right:>right_width$}");
+            text.chars().take(half).collect::<String>()
+        };
+
+        let header = format!(
+            "{}{}",
+            side(
+                "Size".to_owned(),
+                "Bid ".to_owned(),
+                size_width,
+                price_width
+            ),
+            side(
+                " Ask".to_owned(),
+                "Size".to_owned(),
+                price_width,
+                size_width
+            ),
+        );
+        let header_style = theme.text().add_modifier(Modifier::BOLD);
+        let mut text = vec![Spans::from(Span::styled(header, header_style))];
+
+        // Bars show the cumulative size relative to the deepest shown level
+        let levels = inner.height as usize - 1;
+        let cumulative = |sizes: Vec<f64>| {
+            sizes
+                .into_iter()
+                .scan(0.0, |total, size| {
+                    *total += size;
+                    Some(*total)
+                })
+                .collect::<Vec<f64>>()
+        };
+        let bids = &self.book.bids[..self.book.bids.len().min(levels)];
+        let asks = &self.book.asks[..self.book.asks.len().min(levels)];
+        let bid_totals = cumulative(bids.iter().map(|bid| bid.qty).collect());
+        let ask_totals = cumulative(asks.iter().map(|ask| ask.qty).collect());
+        let max_total = bid_totals
+            .iter()
+            .chain(&ask_totals)
+            .copied()
+            .fold(0.0, f64::max);
+        let bar = |total: f64| {
+            if max_total > 0.0 {
+                (total / max_total * half as f64).round() as usize
+            } else {
+                0
+            }
+        };
+
+        for i in 0..bids.len().max(asks.len()) {
+            let mut spans = Vec::new();
+            match bids.get(i) {
+                Some(bid) => {
+                    let bid_text = side(
+                        self.quantities.format(bid.qty),
+                        format!("{} ", self.prices.format(bid.price)),
+                        size_width,
+                        price_width,
+                    );
+                    let style = Style::default().fg(theme.price_up);
+                    spans.extend(Self::bar_spans(bid_text, bar(bid_totals[i]), true, style));
+                }
+                None => spans.push(Span::raw(" ".repeat(half))),
+            }
+            if let Some(ask) = asks.get(i) {
+                let ask_text = side(
+                    format!(" {}", self.prices.format(ask.price)),
+                    self.quantities.format(ask.qty),
+                    price_width,
+                    size_width,
+                );
+                let style = Style::default().fg(theme.price_down);
+                spans.extend(Self::bar_spans(ask_text, bar(ask_totals[i]), false, style));
+            }
+            text.push(Spans::from(spans));
+        }
+
+        frame.render_widget(Paragraph::new(text), inner);
+    }
+}
+
+impl DynamicObject<(Arc<OrderBook>, Option<Instant>)> for Depth {
+    fn update(&mut self, (book, updated): (Arc<OrderBook>, Option<Instant>)) {
+        self.book = book;
+        self.updated = updated;
+    }
+}
+
+impl Default for Depth {
+    fn default() -> Self {
+        Self {
+            area: Default::default(),
+            book: Arc::new(OrderBook {
+                last_update_id: 0,
+                bids: Vec::new(),
+                asks: Vec::new(),
+            }),
+            updated: None,
+            prices: Precision::default(),
+            quantities: Precision::default(),
+        }
+    }
+}
+
+/// Time and sales of the main symbol, the newest trade at the top.
+#[derive(Default)]
+struct TradeTape {
+    area: Rect,
+    /// The latest trades, the newest first.
+    trades: Vec<Trade>,
+    /// Trades with at least this notional value are highlighted.
+    large_notional: Decimal,
+    prices: Precision,
+    quantities: Precision,
+
+    /// Number of trades scrolled down from the newest one.
+    offset: usize,
+}
+
+impl TradeTape {
+    const PRICE_WIDTH: usize = 12;
+    const QTY_WIDTH: usize = 12;
+    const NOTIONAL_WIDTH: usize = 12;
+
+    fn visible_rows(&self) -> usize {
+        // Without the borders and the header
+        self.area.height.saturating_sub(3) as usize
+    }
+
+    fn clamp_offset(&mut self) {
+        let max_offset = self.trades.len().saturating_sub(self.visible_rows());
+        self.offset = self.offset.min(max_offset);
+    }
+}
+
+impl Object for TradeTape {
+    fn update_area(&mut self, new_area: Rect) {
+        self.area = new_area;
+        self.clamp_offset();
+    }
+
+    fn render<B: Backend>(&self, frame: &mut Frame<B>, theme: &Theme, focused: bool) {
+        let header = format!(
+            "{:<8}  {:>pw$}{:>qw$}{:>nw$}",
+            "Time",
+            "Price",
+            "Size",
+            "Value",
+            pw = Self::PRICE_WIDTH,
+            qw = Self::QTY_WIDTH,
+            nw = Self::NOTIONAL_WIDTH,
+        );
+        let header_style = theme.text().add_modifier(Modifier::BOLD);
+        let mut text = vec![Spans::from(Span::styled(header, header_style))];
+
+        let visible = self
+            .trades
+            .iter()
+            .skip(self.offset)
+            .take(self.visible_rows());
+        for trade in visible {
+            // Sells hit the resting buy orders of the makers
+            let (side, color) = if trade.buyer_maker {
+                ("S", theme.price_down)
+            } else {
+                ("B", theme.price_up)
+            };
+            let notional = trade.notional();
+            let mut style = Style::default().fg(color);
+            if notional >= Some(self.large_notional) {
+                style = style.add_modifier(Modifier::BOLD | Modifier::REVERSED);
+            }
+            text.push(Spans::from(Span::styled(
+                format!(
+                    "{} {side}{:>pw$}{:>qw$}{:>nw$}",
+                    format_clock(trade.time / 1000),
+                    self.prices.format(trade.price),
+                    self.quantities.format(trade.qty),
+                    Precision::decimals(0).format(notional.map_or(f64::NAN, Decimal::to_f64)),
+                    pw = Self::PRICE_WIDTH,
+                    qw = Self::QTY_WIDTH,
+                    nw = Self::NOTIONAL_WIDTH,
+                ),
+                style,
+            )));
+        }
+
+        let paragraph = Paragraph::new(text).block(panel_block("Trades", theme, focused));
+        frame.render_widget(paragraph, self.area);
+    }
+}
+
+impl DynamicObject<Vec<Trade>> for TradeTape {
+    fn update(&mut self, data: Vec<Trade>) {
+        // Keep the same trades in view if the user has scrolled down
+        if self.offset > 0 {
+            let new_trades = data
+                .iter()
+                .take_while(|trade| self.trades.first().is_none_or(|t| trade.time > t.time))
+                .count();
+            self.offset += new_trades;
+        }
+        self.trades = data;
+        self.clamp_offset();
+    }
+}
+
+impl ScrollableObject for TradeTape {
+    fn scroll(&mut self, direction: ScrollDirection) {
+        self.offset = match direction {
+            ScrollDirection::Up => self.offset.saturating_sub(1),
+            ScrollDirection::Down => self.offset + 1,
+        };
+        self.clamp_offset();
+    }
+}
+
+/// Errors and connection changes which happened while the bot was running,
+/// the newest at the bottom.
+#[derive(Default)]
+struct Log {
+    area: Rect,
+    entries: Vec<LogEntry>,
+
+    /// Number of entries scrolled up from the newest one.
+    scroll: usize,
+}
+
+impl Log {
+    const MAX_ENTRIES: usize = 200;
+
+    /// Adds the `message` at the bottom, highlighted if it is an `error`.
+    ///
+    /// Messages which are already in the log are moved to the bottom and counted
+    /// so errors repeated every tick don't push out the other ones.
+    fn push(&mut self, message: String, error: bool) {
+        let count = match self.entries.iter().position(|e| e.message == message) {
+            Some(index) => self.entries.remove(index).count + 1,
+            None => {
+                // Keep the same entries in view if the user has scrolled up
+                if self.scroll > 0 {
+                    self.scroll += 1;
+                }
+                1
+            }
+        };
+        let seconds = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        crate::crash::remember_log_line(format!("{} {message}", format_clock(seconds)));
+        self.entries.push(LogEntry {
+            time: SystemTime::now(),
+            message,
+            count,
+            error,
+        });
+        if self.entries.len() > Self::MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.clamp_scroll();
+    }
+
+    fn clamp_scroll(&mut self) {
+        self.scroll = self.scroll.min(self.entries.len().saturating_sub(1));
+    }
+}
+
+impl Object for Log {
+    fn update_area(&mut self, new_area: Rect) {
+        self.area = new_area;
+    }
+
+    fn render<B: Backend>(&self, frame: &mut Frame<B>, theme: &Theme, focused: bool) {
+        let error_style = Style::default().fg(theme.price_down);
+        let items = self
+            .entries
+            .iter()
+            .rev()
+            .skip(self.scroll)
+            .map(|entry| {
+                let seconds = entry
+                    .time
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                let mut line = format!("{} {}", format_clock(seconds), entry.message);
+                if entry.count > 1 {
+                    line += &format!(" (x{})", entry.count);
+                }
+                let style = if entry.error {
+                    error_style
+                } else {
+                    theme.text()
+                };
+                ListItem::new(line).style(style)
+            })
+            .collect::<Vec<ListItem>>();
+
+        let title = match self.entries.iter().filter(|entry| entry.error).count() {
+            0 => "Log".to_owned(),
+            count => format!("Log - {count} errors"),
+        };
+        let list = List::new(items)
+            .block(panel_block(&title, theme, focused))
+            .start_corner(Corner::BottomLeft);
+        frame.render_widget(list, self.area);
+    }
+}
+
+impl ScrollableObject for Log {
+    fn scroll(&mut self, direction: ScrollDirection) {
+        self.scroll = match direction {
+            ScrollDirection::Up => self.scroll + 1,
+            ScrollDirection::Down => self.scroll.saturating_sub(1),
+        };
+        self.clamp_scroll();
+    }
+}
+
+struct LogEntry {
+    /// When the message was last logged.
+    time: SystemTime,
+    message: String,
+    /// How many times the message was logged.
+    count: usize,
+    error: bool,
+}
+
+/// TUI objects whose content can be scrolled with the mouse wheel or keys.
+trait ScrollableObject: Object {
+    fn scroll(&mut self, direction: ScrollDirection);
 }
 
 /// Every TUI object which has constantly changing
@@ -263,13 +1871,63 @@ trait Object {
     fn update_area(&mut self, new_area: Rect);
 
     /// Renders the object to the provided [`Frame`] or in other words UI.
-    fn render<B: Backend>(&self, frame: &mut Frame<B>);
+    ///
+    /// Focused objects are drawn with a thicker and highlighted border.
+    fn render<B: Backend>(&self, frame: &mut Frame<B>, theme: &Theme, focused: bool);
+}
+
+#[test]
+fn live_stats_drop_low_priority_fields() {
+    let field = |priority, value: &str| {
+        StatsField::new(priority, "x: ", value.to_owned(), Style::default())
+    };
+    let fields = || vec![field(0, "aaaa"), field(2, "bb"), field(1, "cccc")];
+
+    // The fields are 7, 5 and 7 cells wide and separated by 3 cells
+    let fitted = LivePrice::fit_fields(fields(), 17, 1);
+    let priorities = fitted[0].iter().map(|f| f.priority).collect::<Vec<u8>>();
+    assert_eq!(priorities, [0, 1]);
+
+    let fitted = LivePrice::fit_fields(fields(), 17, 2);
+    assert_eq!(fitted.len(), 2);
+    assert_eq!(fitted[1][0].priority, 1);
+
+    assert!(LivePrice::fit_fields(fields(), 5, 1)[0].is_empty());
 }
 
 #[test]
-fn testičje() {
-    let a = 145.2674445623536789;
-    let b = a.to_string();
-    println!("a: {}", a);
-    println!("b: {}", b);
+fn watchlist_drag_pans_trends() {
+    let row = |history: Vec<f64>| WatchlistRow {
+        order: 0,
+        symbol: Symbol::new("BTCUSDT").unwrap(),
+        stats: Arc::new(crate::bot::DEFAULT_PRICE_STATS),
+        history,
+        precision: Precision::default(),
+    };
+    let mut watchlist = Watchlist::default();
+    // The trends are 5 columns wide starting at column 36
+    watchlist.update_area(Rect::new(0, 0, 42, 5));
+    watchlist.update(vec![row((0..8).map(f64::from).collect())]);
+    assert_eq!(watchlist.sparkline_columns(), (36, 5));
+
+    // Dragging the other columns doesn't pan
+    watchlist.start_drag(10);
+    watchlist.drag(20);
+    assert_eq!(watchlist.pan, 0);
+
+    watchlist.start_drag(37);
+    watchlist.drag(39);
+    assert_eq!(watchlist.pan, 2);
+    assert_eq!(
+        watchlist.rows[0].sparkline(5, watchlist.pan),
+        [0, 25, 50, 75, 100]
+    );
+    // The oldest prices stay in view
+    watchlist.drag(45);
+    assert_eq!(watchlist.pan, 3);
+    watchlist.drag(40);
+    assert_eq!(watchlist.pan, 0);
+    watchlist.end_drag();
+    watchlist.drag(45);
+    assert_eq!(watchlist.pan, 0);
 }